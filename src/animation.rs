@@ -0,0 +1,424 @@
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::image::PngOptions;
+use crate::matrix::Matrix;
+use crate::motion::{Decomposed, Motion, MotionError};
+use crate::tonemap::ToneMap;
+use crate::tuple::Tuple;
+use crate::world::World;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+
+#[derive(Debug, PartialEq)]
+pub enum AnimationError {
+    InvalidTime(f64),
+    InvalidFrameRate(f64),
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnimationError::InvalidTime(time) => write!(f, "keyframe time {} isn't finite", time),
+            AnimationError::InvalidFrameRate(fps) => {
+                write!(f, "frame rate {} isn't positive and finite", fps)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnimationError {}
+
+// values that can be blended between keyframes
+pub trait Interpolate {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &f64, t: f64) -> f64 {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Tuple {
+    fn interpolate(&self, other: &Tuple, t: f64) -> Tuple {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Color, t: f64) -> Color {
+        *self + (*other - *self) * t
+    }
+}
+
+// transforms are blended as translation, rotation and scale so that
+// rotations don't shear on the way
+impl Interpolate for Matrix {
+    fn interpolate(&self, other: &Matrix, t: f64) -> Matrix {
+        Decomposed::new(self)
+            .interpolate(&Decomposed::new(other), t)
+            .to_matrix()
+    }
+}
+
+// how a track moves from one keyframe to the next
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    // a css style cubic bezier through (0, 0), (x1, y1), (x2, y2), (1, 1)
+    Bezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    pub const EASE_IN: Easing = Easing::Bezier(0.42, 0.0, 1.0, 1.0);
+    pub const EASE_OUT: Easing = Easing::Bezier(0.0, 0.0, 0.58, 1.0);
+    pub const EASE_IN_OUT: Easing = Easing::Bezier(0.42, 0.0, 0.58, 1.0);
+
+    // maps the fraction of time elapsed to the fraction of the change made
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::Bezier(x1, y1, x2, y2) => {
+                let curve = |a: f64, b: f64, s: f64| {
+                    3.0 * a * s * (1.0 - s).powi(2) + 3.0 * b * s.powi(2) * (1.0 - s) + s.powi(3)
+                };
+
+                // x is monotonic for control points in [0, 1], so bisect
+                // for the parameter that gives our time
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..50 {
+                    let mid = (low + high) / 2.0;
+                    if curve(x1, x2, mid) < t {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                curve(y1, y2, (low + high) / 2.0)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    // shapes the segment arriving at this key
+    pub easing: Easing,
+}
+
+// a value over time, held at the first and last keys outside their range
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate + Clone> Track<T> {
+    // a track that starts at `value` at time 0
+    pub fn new(value: T) -> Track<T> {
+        Track {
+            keys: vec![Keyframe {
+                time: 0.0,
+                value,
+                easing: Easing::Linear,
+            }],
+        }
+    }
+
+    pub fn key(mut self, time: f64, value: T, easing: Easing) -> Result<Track<T>, AnimationError> {
+        if !time.is_finite() {
+            return Err(AnimationError::InvalidTime(time));
+        }
+
+        let index = self.keys.iter().take_while(|k| k.time <= time).count();
+        self.keys.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+        Ok(self)
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    pub fn value_at(&self, time: f64) -> T {
+        let first = &self.keys[0];
+        let last = &self.keys[self.keys.len() - 1];
+        if time <= first.time {
+            return first.value.clone();
+        }
+        if time >= last.time {
+            return last.value.clone();
+        }
+
+        let next = self.keys.iter().position(|k| k.time > time).unwrap();
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        a.value.interpolate(&b.value, b.easing.apply(t))
+    }
+}
+
+impl Track<Matrix> {
+    // the track's transforms between `open` and `close`, for motion blur.
+    // eased segments are followed linearly between the keys that fall
+    // inside the shutter.
+    pub fn motion(&self, open: f64, close: f64) -> Result<Motion, MotionError> {
+        let mut keys = vec![(open, self.value_at(open))];
+        for k in self.keys.iter().filter(|k| k.time > open && k.time < close) {
+            keys.push((k.time, k.value));
+        }
+        keys.push((close, self.value_at(close)));
+        Motion::keyframes(keys)
+    }
+}
+
+// renders a numbered run of frames, each built by a closure given the
+// frame's time in seconds, and saves them as png after tone mapping
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameSequence {
+    pub frames: usize,
+    pub fps: f64,
+    pub tone_map: ToneMap,
+    pub png: PngOptions,
+}
+
+impl FrameSequence {
+    pub fn new(frames: usize, fps: f64) -> Result<FrameSequence, AnimationError> {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(AnimationError::InvalidFrameRate(fps));
+        }
+
+        Ok(FrameSequence {
+            frames,
+            fps,
+            tone_map: ToneMap::default(),
+            png: PngOptions::default(),
+        })
+    }
+
+    pub fn with_tone_map(self, tone_map: ToneMap) -> FrameSequence {
+        FrameSequence { tone_map, ..self }
+    }
+
+    pub fn with_png_options(self, png: PngOptions) -> FrameSequence {
+        FrameSequence { png, ..self }
+    }
+
+    pub fn time(&self, frame: usize) -> f64 {
+        frame as f64 / self.fps
+    }
+
+    // `prefix` may include a directory, which must already exist
+    pub fn file_name(&self, prefix: &str, frame: usize) -> String {
+        let digits = self.frames.saturating_sub(1).to_string().len().max(4);
+        format!("{}_{:0digits$}.png", prefix, frame, digits = digits)
+    }
+
+    pub fn render<F>(&self, prefix: &str, mut scene: F) -> std::io::Result<Vec<String>>
+    where
+        F: FnMut(f64) -> (Camera, World),
+    {
+        let mut names = vec![];
+        for frame in 0..self.frames {
+            let (camera, world) = scene(self.time(frame));
+            let canvas = camera.render(world);
+
+            let png = self.tone_map.apply(&canvas).to_png(self.png);
+            let name = self.file_name(prefix, frame);
+            let mut file = File::create(&name)?;
+            file.write_all(&png)?;
+            names.push(name);
+        }
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+    use crate::image::{BitDepth, Gamma};
+    use crate::tonemap::ToneMapOperator;
+    use crate::transformations::{identity, rotate_y, translate, view_transform};
+    use crate::tuple::{point, vector};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn linear_tracks_blend_between_keys() {
+        let track = Track::new(1.0)
+            .key(2.0, 3.0, Easing::Linear)
+            .unwrap()
+            .key(4.0, -1.0, Easing::Linear)
+            .unwrap();
+
+        assert!(track.value_at(-1.0) == 1.0);
+        assert!(track.value_at(1.0) == 2.0);
+        assert!(track.value_at(3.0) == 1.0);
+        assert!(track.value_at(10.0) == -1.0);
+    }
+
+    #[test]
+    fn keys_are_kept_in_time_order() {
+        let track = Track::new(point(0, 0, 0))
+            .key(2.0, point(2, 0, 0), Easing::Linear)
+            .unwrap()
+            .key(1.0, point(0, 4, 0), Easing::Linear)
+            .unwrap();
+
+        let times: Vec<f64> = track.keys().iter().map(|k| k.time).collect();
+        assert!(times == vec![0.0, 1.0, 2.0]);
+        assert!(track.value_at(1.5) == point(1, 2, 0));
+    }
+
+    #[test]
+    fn bezier_easing_starts_and_ends_slowly() {
+        let ease = Easing::EASE_IN_OUT;
+
+        assert!(ease.apply(0.0).abs() < 1e-6);
+        assert!((ease.apply(1.0) - 1.0).abs() < 1e-6);
+        assert!((ease.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!(ease.apply(0.1) < 0.1);
+        assert!(ease.apply(0.9) > 0.9);
+        assert!((Easing::Bezier(0.25, 0.25, 0.75, 0.75).apply(0.3) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn easing_applies_to_the_segment_arriving_at_a_key() {
+        let track = Track::new(0.0)
+            .key(1.0, 1.0, Easing::EASE_IN)
+            .unwrap()
+            .key(2.0, 2.0, Easing::Linear)
+            .unwrap();
+
+        assert!(track.value_at(0.5) < 0.5);
+        assert!(track.value_at(1.5) == 1.5);
+    }
+
+    #[test]
+    fn colors_interpolate_per_channel() {
+        let track = Track::new(color(0, 0, 0))
+            .key(1.0, color(1, 0.5, 0), Easing::Linear)
+            .unwrap();
+
+        assert!(track.value_at(0.5) == color(0.5, 0.25, 0));
+    }
+
+    #[test]
+    fn transforms_interpolate_through_their_rotation() {
+        let track = Track::new(identity())
+            .key(1.0, translate(2, 0, 0) * rotate_y(PI / 2.0), Easing::Linear)
+            .unwrap();
+
+        assert!(track.value_at(0.5) == translate(1, 0, 0) * rotate_y(PI / 4.0));
+    }
+
+    #[test]
+    fn transform_tracks_give_motion_over_a_shutter() {
+        let track = Track::new(identity())
+            .key(1.0, translate(1, 0, 0), Easing::Linear)
+            .unwrap()
+            .key(2.0, translate(1, 3, 0), Easing::Linear)
+            .unwrap();
+
+        let motion = track.motion(0.5, 1.5).unwrap();
+
+        assert!(motion.transform_at(0.5) == translate(0.5, 0, 0));
+        assert!(motion.transform_at(1.0) == translate(1, 0, 0));
+        assert!(motion.transform_at(1.5) == translate(1, 1.5, 0));
+    }
+
+    #[test]
+    fn frames_are_numbered_with_padding() {
+        let sequence = FrameSequence::new(24, 12.0).unwrap();
+
+        assert!(sequence.time(6) == 0.5);
+        assert!(sequence.file_name("out/spin", 7) == "out/spin_0007.png");
+        assert!(
+            FrameSequence::new(12000, 24.0)
+                .unwrap()
+                .file_name("spin", 7)
+                == "spin_00007.png"
+        );
+    }
+
+    #[test]
+    fn rendering_a_sequence_writes_each_frame() {
+        let dir = std::env::temp_dir().join(format!("frames-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let prefix = dir.join("frame");
+        let from = Track::new(point(0, 0, -5))
+            .key(1.0, point(0, 0, -3), Easing::Linear)
+            .unwrap();
+
+        let mut times = vec![];
+        let names = FrameSequence::new(3, 2.0)
+            .unwrap()
+            .render(prefix.to_str().unwrap(), |time| {
+                times.push(time);
+                let t = view_transform(from.value_at(time), point(0, 0, 0), vector(0, 1, 0));
+                (Camera::new(4, 4, PI / 2.0, t), World::default())
+            })
+            .unwrap();
+
+        assert!(times == vec![0.0, 0.5, 1.0]);
+        assert!(names.len() == 3);
+        for name in names.iter() {
+            let data = std::fs::read(name).unwrap();
+            assert!(data.starts_with(b"\x89PNG"));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sequences_use_their_tone_map_and_png_options() {
+        let dir = std::env::temp_dir().join(format!("frames-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let prefix = dir.join("frame");
+        let tone_map = ToneMap::new(-1.0, ToneMapOperator::Clamp, Gamma::Linear);
+        let png = PngOptions {
+            bit_depth: BitDepth::Sixteen,
+            gamma: Gamma::Linear,
+        };
+        let t = view_transform(point(0, 0, -5), point(0, 0, 0), vector(0, 1, 0));
+
+        let names = FrameSequence::new(1, 24.0)
+            .unwrap()
+            .with_tone_map(tone_map)
+            .with_png_options(png)
+            .render(prefix.to_str().unwrap(), |_| {
+                (Camera::new(11, 11, PI / 2.0, t), World::default())
+            })
+            .unwrap();
+
+        let expected = tone_map.apply(&Camera::new(11, 11, PI / 2.0, t).render(World::default()));
+        assert!(std::fs::read(&names[0]).unwrap() == expected.to_png(png));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sequences_need_a_positive_finite_frame_rate() {
+        assert!(FrameSequence::new(10, 0.0) == Err(AnimationError::InvalidFrameRate(0.0)));
+        assert!(FrameSequence::new(10, -24.0) == Err(AnimationError::InvalidFrameRate(-24.0)));
+        assert!(FrameSequence::new(10, f64::NAN).is_err());
+        assert!(FrameSequence::new(10, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn key_times_must_be_finite() {
+        let track = Track::new(0.0);
+
+        assert!(
+            track.clone().key(f64::INFINITY, 1.0, Easing::Linear)
+                == Err(AnimationError::InvalidTime(f64::INFINITY))
+        );
+        assert!(track.key(f64::NAN, 1.0, Easing::Linear).is_err());
+    }
+}
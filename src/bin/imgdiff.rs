@@ -0,0 +1,50 @@
+use ray_tracer_lib::*;
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+use std::process;
+
+const USAGE: &str = "usage: imgdiff <expected> <actual> [heatmap.png] [--tolerance <max error>]";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut paths = vec![];
+    let mut tolerance = 0.0;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tolerance" => {
+                tolerance = args.next().ok_or(USAGE)?.parse()?;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() < 2 || paths.len() > 3 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let expected = Canvas::open(&paths[0])?;
+    let actual = Canvas::open(&paths[1])?;
+    let comparison = compare(&expected, &actual)?;
+
+    println!("rmse:             {:.6}", comparison.rmse);
+    println!("psnr:             {:.2} dB", comparison.psnr);
+    println!("max error:        {:.6}", comparison.max_error);
+    println!(
+        "differing pixels: {} of {}",
+        comparison.differing_pixels,
+        expected.width * expected.height
+    );
+
+    if let Some(path) = paths.get(2) {
+        let png = comparison.heatmap().to_png(PngOptions::default());
+        let mut file = File::create(path)?;
+        file.write_all(&png)?;
+    }
+
+    if !comparison.within(tolerance) {
+        process::exit(1);
+    }
+    Ok(())
+}
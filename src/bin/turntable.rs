@@ -0,0 +1,13 @@
+use ray_tracer_lib::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let sequence = FrameSequence::new(48, 24.0)?;
+
+    std::fs::create_dir_all("turntable")?;
+    // blur the ball over half a frame
+    let exposure = 0.5 / sequence.fps;
+    sequence.render("turntable/frame", |time| {
+        scenes::turntable(time, exposure, 320, 180)
+    })?;
+    Ok(())
+}
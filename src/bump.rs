@@ -0,0 +1,123 @@
+use crate::mapping::Mapping;
+use crate::noise::Noise;
+use crate::object::Object;
+use crate::texture::Texture;
+use crate::tuple::{vector, Tuple};
+
+// perturbs the shading normal to fake surface detail without extra geometry
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bump {
+    None,
+    // a height field of noise sampled at the given frequency, tilting the
+    // normal by the given strength
+    Noise(Noise, f64, f64),
+    // a tangent-space normal map, looked up with the mapping, at the given strength
+    NormalMap(Texture, Mapping, f64),
+}
+
+impl Bump {
+    pub fn perturb(&self, object: &dyn Object, point: Tuple, normal: Tuple) -> Tuple {
+        match self {
+            Bump::None => normal,
+            Bump::Noise(noise, frequency, strength) => {
+                let object_point = object.inverse() * point;
+                let height = |offset: Tuple| noise.fbm((object_point + offset) * *frequency);
+
+                let h = 0.001;
+                let gradient = vector(
+                    height(vector(h, 0, 0)) - height(vector(-h, 0, 0)),
+                    height(vector(0, h, 0)) - height(vector(0, -h, 0)),
+                    height(vector(0, 0, h)) - height(vector(0, 0, -h)),
+                ) / (2.0 * h);
+
+                let mut world_gradient = object.inverse().transpose() * gradient;
+                world_gradient.w = 0.0;
+
+                // only the part of the slope that lies along the surface tilts the normal
+                let tangential = world_gradient - normal * world_gradient.dot(normal);
+                (normal - tangential * *strength).normalize()
+            }
+            Bump::NormalMap(texture, mapping, strength) => {
+                let object_point = object.inverse() * point;
+                let (u, v) = mapping.map(object_point);
+                let c = texture.color_at(u, v);
+
+                let tangent = object.tangent_at(point);
+                let tangent = (tangent - normal * tangent.dot(normal)).normalize();
+                let bitangent = tangent.cross(&normal);
+
+                let x = (c.0 * 2.0 - 1.0) * strength;
+                let y = (c.1 * 2.0 - 1.0) * strength;
+                let z = c.2 * 2.0 - 1.0;
+                (tangent * x + bitangent * y + normal * z).normalize()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::color::color;
+    use crate::shapes::{Plane, Sphere};
+    use crate::texture::{Filter, Wrap};
+    use crate::transformations::rotate_z;
+    use crate::tuple::point;
+    use std::f64::consts::PI;
+
+    fn flat_map(c: crate::color::Color) -> Texture {
+        let mut image = Canvas::new(1, 1);
+        image.write_pixel(0, 0, c);
+        Texture::new(image, Filter::Nearest, Wrap::Repeat)
+    }
+
+    #[test]
+    fn no_bump_leaves_the_normal_alone() {
+        let s = Sphere::default();
+        let n = vector(0, 1, 0);
+
+        assert!(Bump::None.perturb(&s, point(0, 1, 0), n) == n);
+    }
+
+    #[test]
+    fn flat_normal_map_leaves_the_normal_alone() {
+        let p = Plane::default();
+        let bump = Bump::NormalMap(flat_map(color(0.5, 0.5, 1)), Mapping::Planar, 1.0);
+
+        assert!(bump.perturb(&p, point(0.3, 0, 0.7), vector(0, 1, 0)) == vector(0, 1, 0));
+    }
+
+    #[test]
+    fn normal_map_is_relative_to_the_tangent_frame() {
+        let p = Plane::default();
+        let along_u = Bump::NormalMap(flat_map(color(1, 0.5, 0.5)), Mapping::Planar, 1.0);
+        let along_v = Bump::NormalMap(flat_map(color(0.5, 1, 0.5)), Mapping::Planar, 1.0);
+
+        assert!(along_u.perturb(&p, point(0, 0, 0), vector(0, 1, 0)) == vector(1, 0, 0));
+        assert!(along_v.perturb(&p, point(0, 0, 0), vector(0, 1, 0)) == vector(0, 0, 1));
+    }
+
+    #[test]
+    fn normal_map_follows_the_object_transformation() {
+        let mut p = Plane::default();
+        p.transform(rotate_z(PI / 2.0));
+        let bump = Bump::NormalMap(flat_map(color(1, 0.5, 0.5)), Mapping::Planar, 1.0);
+
+        let n = bump.perturb(&p, point(0, 0, 0), vector(-1, 0, 0));
+        assert!(n == vector(0, 1, 0));
+    }
+
+    #[test]
+    fn noise_bump_tilts_the_normal() {
+        let s = Sphere::default();
+        let bump = Bump::Noise(Noise::perlin(3), 4.0, 0.5);
+        let p = point(0, 0, -1);
+        let normal = vector(0, 0, -1);
+
+        let n = bump.perturb(&s, p, normal);
+        assert!(n != normal);
+        assert!((n.magnitude() - 1.0).abs() < 0.0001);
+        assert!(n.dot(normal) > 0.0);
+    }
+}
@@ -1,9 +1,16 @@
 use crate::canvas::{canvas, Canvas};
+use crate::color::{Color, BLACK};
+use crate::lens::Lens;
 use crate::matrix::Matrix;
 use crate::ray::{ray, Ray};
-use crate::tuple::point;
-use crate::world::World;
+use crate::spectrum::{spectral_samples, MIN_SPECTRAL_SAMPLES};
+use crate::tuple::{point, vector, Tuple};
+use crate::world::{Sample, World};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
 use std::time::Instant;
+use uuid::Uuid;
 #[allow(dead_code)]
 pub struct Camera {
     h_size: usize,
@@ -13,6 +20,26 @@ pub struct Camera {
     half_width: f64,
     half_height: f64,
     pixel_size: f64,
+    projection: Projection,
+    lens: Option<Lens>,
+    shift: (f64, f64),
+    shutter: (f64, f64),
+    samples: usize,
+    spectral_samples: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // parallel rays, each starting on the view plane
+    Orthographic,
+    // a 360 degree latitude/longitude panorama, looking forward at the centre
+    Equirectangular,
+    // equidistant fisheye with the given field of view across the image circle
+    Fisheye(f64),
+    // six 90 degree views unfolded into a horizontal cross: up, then left,
+    // front, right and back, then down
+    CubeMap,
 }
 
 impl Camera {
@@ -23,8 +50,71 @@ impl Camera {
         transform: U,
     ) -> Camera {
         let fov = field_of_view.into();
-
         let half_view = (fov / 2.0).tan();
+
+        Self::with_view_plane(
+            h_size,
+            v_size,
+            half_view,
+            Projection::Perspective,
+            transform,
+        )
+    }
+
+    // view_width is the size of the view along its longer side, in world units
+    pub fn orthographic<T: Into<f64>, U: Into<Option<Matrix>>>(
+        h_size: usize,
+        v_size: usize,
+        view_width: T,
+        transform: U,
+    ) -> Camera {
+        let half_view = view_width.into() / 2.0;
+
+        Self::with_view_plane(
+            h_size,
+            v_size,
+            half_view,
+            Projection::Orthographic,
+            transform,
+        )
+    }
+
+    pub fn equirectangular<U: Into<Option<Matrix>>>(
+        h_size: usize,
+        v_size: usize,
+        transform: U,
+    ) -> Camera {
+        Self::with_view_plane(h_size, v_size, 1.0, Projection::Equirectangular, transform)
+    }
+
+    pub fn fisheye<T: Into<f64>, U: Into<Option<Matrix>>>(
+        h_size: usize,
+        v_size: usize,
+        field_of_view: T,
+        transform: U,
+    ) -> Camera {
+        let projection = Projection::Fisheye(field_of_view.into());
+        Self::with_view_plane(h_size, v_size, 1.0, projection, transform)
+    }
+
+    // the canvas is four faces wide and three high
+    pub fn cube_map<U: Into<Option<Matrix>>>(face_size: usize, transform: U) -> Camera {
+        Self::with_view_plane(
+            face_size * 4,
+            face_size * 3,
+            1.0,
+            Projection::CubeMap,
+            transform,
+        )
+    }
+
+    fn with_view_plane<U: Into<Option<Matrix>>>(
+        h_size: usize,
+        v_size: usize,
+        half_view: f64,
+        projection: Projection,
+        transform: U,
+    ) -> Camera {
         let aspect = h_size as f64 / v_size as f64;
         let mut half_width = half_view * aspect;
         let mut half_height = half_view;
@@ -41,6 +131,12 @@ impl Camera {
             h_size,
             v_size,
             pixel_size,
+            projection,
+            lens: None,
+            shift: (0.0, 0.0),
+            shutter: (0.0, 0.0),
+            samples: 1,
+            spectral_samples: 0,
             half_height,
             half_width,
             transform: match t {
@@ -54,41 +150,333 @@ impl Camera {
         }
     }
 
-    fn ray_for_pixel<A: Into<f64>, B: Into<f64>>(&self, px: A, py: B) -> Ray {
-        let pixel_x = px.into();
-        let pixel_y = py.into();
-        // the offset from the edge of the canvas to the pixel's center
-        let x_offset = (pixel_x + 0.5) * self.pixel_size;
-        let y_offset = (pixel_y + 0.5) * self.pixel_size;
+    // trace this many wavelengths per pixel instead of a single RGB ray, so that
+    // materials with dispersion split light into its colours. 0 renders in RGB,
+    // and other counts are raised to at least MIN_SPECTRAL_SAMPLES.
+    pub fn with_spectral_samples(self, samples: usize) -> Camera {
+        Camera {
+            spectral_samples: match samples {
+                0 => 0,
+                _ => samples.max(MIN_SPECTRAL_SAMPLES),
+            },
+            ..self
+        }
+    }
+
+    // a thin lens gives depth of field; rays from anywhere on its aperture
+    // converge on the plane focal_distance in front of the camera. panoramic
+    // projections have no view plane to focus, so they ignore the lens
+    pub fn with_lens(self, lens: Lens) -> Camera {
+        Camera {
+            lens: Some(lens),
+            ..self
+        }
+    }
+
+    // slides the view plane right and up without turning the camera, in
+    // units of the view plane (which is one unit in front of the camera)
+    pub fn with_shift(self, right: f64, up: f64) -> Camera {
+        Camera {
+            shift: (right, up),
+            ..self
+        }
+    }
+
+    // rays are cast at times spread between the shutter opening and closing,
+    // which blurs objects that have a `Motion`
+    pub fn with_shutter(self, open: f64, close: f64) -> Camera {
+        Camera {
+            shutter: (open, close),
+            ..self
+        }
+    }
+
+    // rays traced per pixel, each through a random point of the pixel and of
+    // the lens. a single sample goes through the pixel's centre.
+    pub fn with_samples(self, samples: usize) -> Camera {
+        Camera {
+            samples: samples.max(1),
+            ..self
+        }
+    }
+
+    // a shorthand for the tests, which know their pixels see something; it
+    // panics for pixels that don't, so it stays out of the public api
+    #[cfg(test)]
+    pub(crate) fn ray_for_pixel<A: Into<f64>, B: Into<f64>>(&self, px: A, py: B) -> Ray {
+        let (px, py) = (px.into(), py.into());
+        self.try_ray_for_pixel(px, py)
+            .unwrap_or_else(|| panic!("pixel ({}, {}) doesn't see anything", px, py))
+    }
+
+    // None for pixels that don't see anything, such as the corners outside a
+    // fisheye's image circle
+    pub fn try_ray_for_pixel<A: Into<f64>, B: Into<f64>>(&self, px: A, py: B) -> Option<Ray> {
+        self.ray_through(px.into() + 0.5, py.into() + 0.5, (0.0, 0.0))
+    }
+
+    // (x, y) is a position on the canvas in pixels and lens_point an offset
+    // on the aperture, in camera space
+    fn ray_through(&self, x: f64, y: f64, lens_point: (f64, f64)) -> Option<Ray> {
+        // the offset from the edge of the canvas to the point
+        let x_offset = x * self.pixel_size;
+        let y_offset = y * self.pixel_size;
 
         //  the untransformed coordinates of the pixel in world space.
         // # (remember that the camera looks toward -z, so +x is to the *left*.)
-        let world_x = self.half_width - x_offset;
-        let world_y = self.half_height - y_offset;
+        let world_x = self.half_width - x_offset - self.shift.0;
+        let world_y = self.half_height - y_offset + self.shift.1;
         // # using the camera matrix, transform the canvas point and the origin, # and then compute the ray's direction vector.
         // # (remember that the canvas is at z=-1)
-        let pixel = self.inverse * point(world_x, world_y, -1);
+        let (pixel, eye) = match self.projection {
+            Projection::Perspective => (point(world_x, world_y, -1), point(0, 0, 0)),
+            Projection::Orthographic => (point(world_x, world_y, -1), point(world_x, world_y, 0)),
+            Projection::Equirectangular => {
+                return self.panoramic_ray(Some(self.equirectangular_direction(x, y)))
+            }
+            Projection::Fisheye(field_of_view) => {
+                return self.panoramic_ray(self.fisheye_direction(x, y, field_of_view))
+            }
+            Projection::CubeMap => return self.panoramic_ray(self.cube_map_direction(x, y)),
+        };
+
+        let (origin, target) = match self.lens {
+            None => (eye, pixel),
+            Some(lens) => {
+                // where the pinhole ray meets the focal plane
+                let focus = eye + (pixel - eye) * lens.focal_distance;
+                (eye + vector(lens_point.0, lens_point.1, 0), focus)
+            }
+        };
+
+        let origin = self.inverse * origin;
+        let direction = (self.inverse * target - origin).normalize();
+        Some(ray(origin, direction))
+    }
+
+    // panoramic rays all start at the camera. directions are in camera
+    // space, where the camera looks toward -z with +x on its left.
+    fn panoramic_ray(&self, direction: Option<Tuple>) -> Option<Ray> {
         let origin = self.inverse * point(0, 0, 0);
-        let direction = (pixel - origin).normalize();
-        ray(origin, direction)
+        direction.map(|d| ray(origin, (self.inverse * d).normalize()))
+    }
+
+    fn equirectangular_direction(&self, x: f64, y: f64) -> Tuple {
+        let longitude = (x / self.h_size as f64 - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - y / self.v_size as f64) * PI;
+
+        let horizontal = vector(-longitude.sin(), 0, -longitude.cos());
+        horizontal * latitude.cos() + vector(0, latitude.sin(), 0)
+    }
+
+    fn fisheye_direction(&self, x: f64, y: f64, field_of_view: f64) -> Option<Tuple> {
+        let radius = self.h_size.min(self.v_size) as f64 / 2.0;
+        let dx = (x - self.h_size as f64 / 2.0) / radius;
+        let dy = (self.v_size as f64 / 2.0 - y) / radius;
+        let r = (dx * dx + dy * dy).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+
+        // the angle from the axis grows linearly with the distance from the centre
+        let theta = r * field_of_view / 2.0;
+        let phi = dy.atan2(dx);
+        Some(vector(
+            -theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            -theta.cos(),
+        ))
+    }
+
+    fn cube_map_direction(&self, x: f64, y: f64) -> Option<Tuple> {
+        let forward = vector(0, 0, -1);
+        let right = vector(-1, 0, 0);
+        let up = vector(0, 1, 0);
+
+        let u = x / self.h_size as f64 * 4.0;
+        let v = y / self.v_size as f64 * 3.0;
+        let (column, row) = (u.floor(), v.floor());
+        // the position on the face, from -1 to 1 left to right and bottom to top
+        let a = (u - column) * 2.0 - 1.0;
+        let b = 1.0 - (v - row) * 2.0;
+
+        // the forward, right and up directions of each face
+        let face = match (row as i32, column as i32) {
+            (0, 1) => Some((up, right, -forward)),
+            (1, 0) => Some((-right, forward, up)),
+            (1, 1) => Some((forward, right, up)),
+            (1, 2) => Some((right, -forward, up)),
+            (1, 3) => Some((-forward, -right, up)),
+            (2, 1) => Some((-up, right, forward)),
+            _ => None,
+        };
+        face.map(|(f, r, u)| f + r * a + u * b)
+    }
+
+    fn spectral_sample_at(world: &World, r: Ray, wavelengths: &[(f64, Color)]) -> Sample {
+        weighted_sum(wavelengths.iter().map(|(wavelength, weight)| {
+            (world.sample_at(r.with_wavelength(*wavelength), 5), *weight)
+        }))
+    }
+
+    fn sample_pixel<R: Rng>(
+        &self,
+        world: &World,
+        x: usize,
+        y: usize,
+        wavelengths: &[(f64, Color)],
+        rng: &mut R,
+    ) -> Sample {
+        let weight = 1.0 / self.samples as f64;
+        weighted_sum((0..self.samples).map(|_| {
+            let (jx, jy, jt) = match self.samples {
+                1 => (0.5, 0.5, 0.5),
+                _ => (rng.gen(), rng.gen(), rng.gen()),
+            };
+            let (open, close) = self.shutter;
+            let time = open + (close - open) * jt;
+            let lens_point = match self.lens {
+                Some(lens) => lens.sample(rng),
+                None => (0.0, 0.0),
+            };
+            let r = self.ray_through(x as f64 + jx, y as f64 + jy, lens_point);
+            let sample = match r.map(|r| r.with_time(time)) {
+                None => Sample::default(),
+                Some(r) => match self.spectral_samples {
+                    0 => world.sample_at(r, 5),
+                    _ => Self::spectral_sample_at(world, r, wavelengths),
+                },
+            };
+            (sample, Color(weight, weight, weight))
+        }))
     }
 
     pub fn render(&self, world: World) -> Canvas {
+        self.render_passes(&world).beauty
+    }
+
+    pub fn render_passes(&self, world: &World) -> RenderResult {
         println!("Beginning render...");
         let time = Instant::now();
-        let mut image = canvas(self.h_size, self.v_size);
+        let mut result = RenderResult::new(self.h_size, self.v_size);
+        let wavelengths = spectral_samples(self.spectral_samples);
         for y in 0..self.v_size {
             for x in 0..self.h_size {
-                let r = self.ray_for_pixel(x as f64, y as f64);
-                let color = world.color_at(r, 5);
-                image.write_pixel(x, y, color);
+                // seeded per pixel so that renders are repeatable
+                let mut rng = StdRng::seed_from_u64((y * self.h_size + x) as u64);
+                let sample = self.sample_pixel(world, x, y, &wavelengths, &mut rng);
+                result.write_sample(x, y, &sample);
             }
         }
         println!("Renderd in {} seconds", time.elapsed().as_secs());
-        image
+        result
+    }
+}
+
+// sums the colour passes of several samples, scaled by their weights, and
+// averages depth, normal and albedo so they line up with the beauty pass.
+// normals aren't renormalised, so they shrink where a pixel spans an edge.
+// the object id is the most common one, favouring the earliest on a tie.
+fn weighted_sum<I: Iterator<Item = (Sample, Color)>>(samples: I) -> Sample {
+    let mut sum = Sample::default();
+    let mut ids: Vec<(Option<Uuid>, usize)> = vec![];
+    for (sample, weight) in samples {
+        // geometry is the same in every channel, so it takes the mean weight
+        let share = (weight.0 + weight.1 + weight.2) / 3.0;
+        sum.color = sum.color + sample.color * weight;
+        sum.direct = sum.direct + sample.direct * weight;
+        sum.reflection = sum.reflection + sample.reflection * weight;
+        sum.refraction = sum.refraction + sample.refraction * weight;
+        sum.depth += sample.depth * share;
+        sum.normal = sum.normal + sample.normal * share;
+        sum.albedo = sum.albedo + sample.albedo * share;
+
+        match ids.iter_mut().find(|(id, _)| *id == sample.object_id) {
+            Some((_, count)) => *count += 1,
+            None => ids.push((sample.object_id, 1)),
+        }
+    }
+
+    // max_by_key keeps the last of equal counts, so search from the back
+    sum.object_id = ids
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .and_then(|(id, _)| *id);
+    sum
+}
+
+// the final image plus auxiliary passes (AOVs) for compositing. normals are
+// in world space and may be negative, so are best kept in a float format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderResult {
+    pub beauty: Canvas,
+    pub depth: Canvas,
+    pub normal: Canvas,
+    pub albedo: Canvas,
+    pub object_id: Canvas,
+    pub direct: Canvas,
+    pub indirect: Canvas,
+    pub reflection: Canvas,
+    pub refraction: Canvas,
+}
+
+impl RenderResult {
+    pub fn new(width: usize, height: usize) -> RenderResult {
+        RenderResult {
+            beauty: canvas(width, height),
+            depth: canvas(width, height),
+            normal: canvas(width, height),
+            albedo: canvas(width, height),
+            object_id: canvas(width, height),
+            direct: canvas(width, height),
+            indirect: canvas(width, height),
+            reflection: canvas(width, height),
+            refraction: canvas(width, height),
+        }
+    }
+
+    pub fn write_sample(&mut self, x: usize, y: usize, sample: &Sample) {
+        let n = sample.normal;
+        self.beauty.write_pixel(x, y, sample.color);
+        self.depth
+            .write_pixel(x, y, Color(sample.depth, sample.depth, sample.depth));
+        self.normal.write_pixel(x, y, Color(n.x, n.y, n.z));
+        self.albedo.write_pixel(x, y, sample.albedo);
+        self.object_id
+            .write_pixel(x, y, sample.object_id.map(id_color).unwrap_or(BLACK));
+        self.direct.write_pixel(x, y, sample.direct);
+        self.indirect.write_pixel(x, y, sample.indirect());
+        self.reflection.write_pixel(x, y, sample.reflection);
+        self.refraction.write_pixel(x, y, sample.refraction);
+    }
+
+    // every pass with a name suitable for a file suffix
+    pub fn passes(&self) -> Vec<(&'static str, &Canvas)> {
+        vec![
+            ("beauty", &self.beauty),
+            ("depth", &self.depth),
+            ("normal", &self.normal),
+            ("albedo", &self.albedo),
+            ("object_id", &self.object_id),
+            ("direct", &self.direct),
+            ("indirect", &self.indirect),
+            ("reflection", &self.reflection),
+            ("refraction", &self.refraction),
+        ]
     }
 }
 
+// a stable colour for an object, used to build mattes from the object id pass
+pub fn id_color(id: Uuid) -> Color {
+    let bytes = id.as_bytes();
+    Color(
+        bytes[0] as f64 / 255.0,
+        bytes[1] as f64 / 255.0,
+        bytes[2] as f64 / 255.0,
+    )
+}
+
 pub fn camera<T: Into<f64>, U: Into<Option<Matrix>>>(
     h_size: usize,
     v_size: usize,
@@ -97,15 +485,23 @@ pub fn camera<T: Into<f64>, U: Into<Option<Matrix>>>(
 ) -> Camera {
     Camera::new(h_size, v_size, field_of_view, transform)
 }
+pub fn orthographic_camera<T: Into<f64>, U: Into<Option<Matrix>>>(
+    h_size: usize,
+    v_size: usize,
+    view_width: T,
+    transform: U,
+) -> Camera {
+    Camera::orthographic(h_size, v_size, view_width, transform)
+}
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::consts::EPSILON;
-    use crate::transformations::{rotate_y, translate, view_transform};
-    use crate::tuple::vector;
-    use std::f64::consts::PI;
+    use crate::transformations::{identity, rotate_y, scale, translate, view_transform};
 
     use crate::color::color;
+    use crate::lens::ApertureShape;
+    use crate::motion::Motion;
     #[test]
     fn constructing_a_camera() {
         let h_size = 160;
@@ -175,4 +571,291 @@ mod tests {
 
         assert!(image.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn spectral_render_matches_rgb_without_dispersion() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, PI / 2.0, view_transform(from, to, up)).with_spectral_samples(8);
+
+        let image = c.render(World::default());
+
+        assert!(image.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn too_few_spectral_samples_are_raised_to_the_minimum() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = || camera(11, 11, PI / 2.0, view_transform(from, to, up));
+
+        assert!(c().with_spectral_samples(0).spectral_samples == 0);
+        assert!(c().with_spectral_samples(1).spectral_samples == MIN_SPECTRAL_SAMPLES);
+        let image = c().with_spectral_samples(1).render(World::default());
+        assert!(image.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn rendering_passes_with_a_camera() {
+        let w = World::default();
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, PI / 2.0, view_transform(from, to, up));
+
+        let result = c.render_passes(&w);
+
+        assert!(result.beauty.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
+        assert!(result.depth.get_pixel(5, 5) == &color(4, 4, 4));
+        assert!(result.normal.get_pixel(5, 5) == &color(0, 0, -1));
+        assert!(result.albedo.get_pixel(5, 5) == &color(0.8, 1.0, 0.6));
+        assert!(result.object_id.get_pixel(5, 5) == &id_color(w.objects[0].id()));
+        assert!(result.direct.get_pixel(5, 5) == result.beauty.get_pixel(5, 5));
+        assert!(result.indirect.get_pixel(5, 5) == &BLACK);
+    }
+
+    #[test]
+    fn passes_are_black_where_nothing_was_hit() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, PI / 2.0, view_transform(from, to, up));
+
+        let result = c.render_passes(&World::default());
+
+        for (_, pass) in result.passes() {
+            assert!(pass.get_pixel(0, 0) == &BLACK);
+        }
+    }
+
+    #[test]
+    fn supersampled_passes_are_averaged_like_the_beauty_pass() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let hit = |id: Uuid| Sample {
+            color: color(1, 1, 1),
+            depth: 4.0,
+            normal: vector(0, 0, -1),
+            albedo: color(0.8, 0.4, 0.2),
+            object_id: Some(id),
+            ..Sample::default()
+        };
+        let half = Color(0.5, 0.5, 0.5);
+
+        let edge = weighted_sum(vec![(hit(a), half), (Sample::default(), half)].into_iter());
+        assert!(edge.color == color(0.5, 0.5, 0.5));
+        assert!(edge.depth == 2.0);
+        assert!(edge.normal == vector(0, 0, -0.5));
+        assert!(edge.albedo == color(0.4, 0.2, 0.1));
+        assert!(edge.object_id == Some(a));
+
+        let third = Color(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0);
+        let samples = vec![(hit(a), third), (hit(b), third), (hit(b), third)];
+        assert!(weighted_sum(samples.into_iter()).object_id == Some(b));
+    }
+
+    #[test]
+    fn orthographic_pixel_size_covers_the_view_width() {
+        let c = orthographic_camera(200, 125, 4, None);
+
+        assert!(c.projection == Projection::Orthographic);
+        assert!((c.pixel_size - 0.02).abs() < EPSILON);
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let c = orthographic_camera(201, 101, 4, None);
+
+        let centre = c.ray_for_pixel(100, 50);
+        assert!(centre.origin == point(0, 0, 0));
+        assert!(centre.direction == vector(0, 0, -1));
+
+        let corner = c.ray_for_pixel(0, 0);
+        assert!(corner.origin == point(1.99005, 0.99502, 0));
+        assert!(corner.direction == vector(0, 0, -1));
+    }
+
+    #[test]
+    fn orthographic_rays_when_the_camera_is_transformed() {
+        let c = orthographic_camera(201, 101, 4, Some(rotate_y(PI / 4.0) * translate(0, -2, 5)));
+        let r = c.ray_for_pixel(100, 50);
+
+        let root_2 = f64::sqrt(2.0);
+        assert!(r.origin == point(0, 2, -5));
+        assert!(r.direction == vector(root_2 / 2.0, 0, -root_2 / 2.0));
+    }
+
+    #[test]
+    fn rendering_a_world_with_an_orthographic_camera() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = orthographic_camera(11, 11, 4, view_transform(from, to, up));
+
+        let image = c.render(World::default());
+
+        assert!(image.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
+        assert!(image.get_pixel(0, 0) == &color(0, 0, 0));
+    }
+
+    #[test]
+    fn lens_rays_converge_on_the_focal_plane() {
+        let c =
+            camera(201, 101, PI / 2.0, None).with_lens(Lens::new(0.5, 4.0, ApertureShape::Circle));
+
+        let centre = c.ray_through(100.5, 50.5, (0.3, -0.2)).unwrap();
+        assert!(centre.origin == point(0.3, -0.2, 0));
+        let distance = (point(0, 0, -4) - centre.origin).magnitude();
+        assert!(centre.position(distance) == point(0, 0, -4));
+
+        let corner = c.ray_through(0.5, 0.5, (-0.4, 0.1)).unwrap();
+        let pinhole = c.ray_for_pixel(0, 0);
+        let focus = pinhole.position(4.0 / -pinhole.direction.z);
+        let to_focus = focus - corner.origin;
+        assert!(corner.direction == to_focus.normalize());
+    }
+
+    #[test]
+    fn a_lens_with_no_aperture_is_a_pinhole() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, PI / 2.0, view_transform(from, to, up)).with_lens(Lens::new(
+            0.0,
+            5.0,
+            ApertureShape::Polygon(6, 0.0),
+        ));
+
+        let image = c.render(World::default());
+
+        assert!(image.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn out_of_focus_renders_are_blurred_but_repeatable() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, PI / 2.0, view_transform(from, to, up))
+            .with_lens(Lens::new(0.5, 20.0, ApertureShape::Circle))
+            .with_samples(16);
+
+        let first = c.render(World::default());
+        let second = c.render(World::default());
+
+        assert!(first == second);
+        assert!(first.get_pixel(5, 5) != &color(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn equirectangular_centre_looks_forward() {
+        let c = Camera::equirectangular(200, 100, None);
+
+        assert!(c.ray_for_pixel(99.5, 49.5).direction == vector(0, 0, -1));
+        assert!(c.ray_for_pixel(149.5, 49.5).direction == vector(-1, 0, 0));
+        assert!(c.ray_for_pixel(49.5, 49.5).direction == vector(1, 0, 0));
+        assert!(c.ray_for_pixel(-0.5, 49.5).direction == vector(0, 0, 1));
+    }
+
+    #[test]
+    fn equirectangular_rows_span_the_poles() {
+        let c = Camera::equirectangular(200, 100, None);
+
+        assert!(c.ray_for_pixel(99.5, -0.5).direction == vector(0, 1, 0));
+        assert!(c.ray_for_pixel(10, 99.5).direction == vector(0, -1, 0));
+    }
+
+    #[test]
+    fn fisheye_is_equidistant_inside_its_circle() {
+        let c = Camera::fisheye(101, 101, PI, None);
+
+        assert!(c.try_ray_for_pixel(50, 50).unwrap().direction == vector(0, 0, -1));
+        // the edge of the circle is 90 degrees off axis
+        assert!(c.try_ray_for_pixel(100.5, 50).unwrap().direction == vector(-1, 0, 0));
+        let halfway = c.try_ray_for_pixel(50, 24.75).unwrap().direction;
+        let root_2 = f64::sqrt(2.0);
+        assert!(halfway == vector(0, root_2 / 2.0, -root_2 / 2.0));
+        assert!(c.try_ray_for_pixel(0, 0).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel (0, 0) doesn't see anything")]
+    fn infallible_rays_panic_outside_the_fisheye_circle() {
+        Camera::fisheye(101, 101, PI, None).ray_for_pixel(0, 0);
+    }
+
+    #[test]
+    fn cube_map_faces_form_a_cross() {
+        let c = Camera::cube_map(10, None);
+        assert!(c.h_size == 40 && c.v_size == 30);
+
+        let centre = |column: u32, row: u32| {
+            let (x, y) = (column as f64 * 10.0 + 5.0, row as f64 * 10.0 + 5.0);
+            c.ray_through(x, y, (0.0, 0.0)).map(|r| r.direction)
+        };
+        let d = |x, y, z| Some(vector(x, y, z));
+        assert!(centre(1, 0) == d(0, 1, 0));
+        assert!(centre(0, 1) == d(1, 0, 0));
+        assert!(centre(1, 1) == d(0, 0, -1));
+        assert!(centre(2, 1) == d(-1, 0, 0));
+        assert!(centre(3, 1) == d(0, 0, 1));
+        assert!(centre(1, 2) == d(0, -1, 0));
+        assert!(centre(0, 0).is_none());
+        assert!(centre(3, 2).is_none());
+    }
+
+    #[test]
+    fn cube_map_faces_meet_at_their_edges() {
+        let c = Camera::cube_map(10, None);
+
+        // bottom edge of the up face and top edge of the front face
+        let up = c.ray_through(15.0, 9.999, (0.0, 0.0)).unwrap().direction;
+        let front = c.ray_through(15.0, 10.001, (0.0, 0.0)).unwrap().direction;
+        assert!((up - front).magnitude() < 0.001);
+
+        // right edge of the front face and left edge of the right face
+        let front = c.ray_through(19.999, 15.0, (0.0, 0.0)).unwrap().direction;
+        let right = c.ray_through(20.001, 15.0, (0.0, 0.0)).unwrap().direction;
+        assert!((front - right).magnitude() < 0.001);
+    }
+
+    #[test]
+    fn rendering_outside_a_fisheye_circle_is_black() {
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = Camera::fisheye(11, 11, PI / 2.0, view_transform(from, to, up));
+
+        let image = c.render(World::default());
+
+        assert!(image.get_pixel(5, 5) == &color(0.38066, 0.47583, 0.2855));
+        assert!(image.get_pixel(0, 0) == &BLACK);
+    }
+
+    #[test]
+    fn shifting_the_view_plane_keeps_the_camera_facing_forward() {
+        let c = camera(201, 101, PI / 2.0, None).with_shift(0.5, -0.25);
+        let r = c.ray_for_pixel(100, 50);
+
+        assert!(r.origin == point(0, 0, 0));
+        assert!(r.direction == vector(-0.5, -0.25, -1).normalize());
+    }
+
+    #[test]
+    fn moving_objects_are_blurred_across_the_shutter() {
+        let mut w = World::default();
+        w.objects[0].set_motion(Motion::new(identity(), translate(4, 0, 0)).unwrap());
+        w.objects[1].set_motion(Motion::new(scale(0.5, 0.5, 0.5), translate(4, 0, 0)).unwrap());
+        let from = point(0, 0, -5);
+        let to = point(0, 0, 0);
+        let up = vector(0, 1, 0);
+        let c = camera(11, 11, PI / 2.0, view_transform(from, to, up));
+
+        let still = c.render(World::default());
+        let blurred = c.with_shutter(0.0, 1.0).with_samples(16).render(w);
+
+        let (s, b) = (still.get_pixel(5, 5), blurred.get_pixel(5, 5));
+        assert!(b.0 < s.0 && b.0 > 0.0);
+    }
 }
@@ -1,7 +1,8 @@
 use super::color::*;
-use std::fmt::Write;
+use super::image::PpmFormat;
 use std::ops::{Index, IndexMut};
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -27,38 +28,9 @@ impl Canvas {
     }
 
     pub fn to_ppm(&self) -> String {
-        let mut buf = String::new();
-
-        // header
-        buf += "P3\n";
-        writeln!(buf, "{} {}", self.width, self.height).unwrap();
-        writeln!(buf, "255").unwrap();
-
-        // body
-        for y in 0..self.height {
-            let row_start_index = y * self.width;
-            let row_end_index = row_start_index + self.width;
-            let row = &self.pixels[row_start_index..row_end_index];
-            let mut line = String::new();
-            for (i, color) in row.iter().enumerate() {
-                write!(
-                    line,
-                    "{} {} {}",
-                    (color.0 * 255.0).min(255.0).max(0.0).round() as i32,
-                    (color.1 * 255.0).min(255.0).max(0.0).round() as i32,
-                    (color.2 * 255.0).min(255.0).max(0.0).round() as i32
-                )
-                .unwrap();
-
-                if i < self.width - 1 {
-                    write!(line, " ").unwrap();
-                }
-            }
-            writeln!(line).unwrap();
-            write!(buf, "{}", line).unwrap();
-        }
-
-        buf
+        let mut buf = vec![];
+        self.write_ppm(&mut buf, PpmFormat::Ascii).unwrap();
+        String::from_utf8(buf).unwrap()
     }
 }
 
@@ -135,25 +107,25 @@ mod tests {
         assert!(lines[5] == "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255");
     }
 
-    // #[test]
-    // fn split_ppm_lines_at_70_chars() {
-    //     let mut canvas = Canvas::new(5, 3);
+    #[test]
+    fn split_ppm_lines_at_70_chars() {
+        let mut canvas = Canvas::new(10, 2);
 
-    //     for y in 0..canvas.height {
-    //         for x in 0..canvas.width {
-    //             canvas.write_pixel(x, y, Color(1.0, 0.6, 0.8));
-    //         }
-    //     }
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                canvas.write_pixel(x, y, Color(1.0, 0.8, 0.6));
+            }
+        }
 
-    //     let ppm = canvas.to_ppm();
+        let ppm = canvas.to_ppm();
 
-    //     let lines: Vec<&str> = ppm.split('\n').collect();
+        let lines: Vec<&str> = ppm.split('\n').collect();
 
-    //     assert!(lines[3] == "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
-    //     assert!(lines[4] == "153 255 204 153 255 204 153 255 204 153 255 204 153");
-    //     assert!(lines[5] == "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
-    //     assert!(lines[6] == "153 255 204 153 255 204 153 255 204 153 255 204 153");
-    // }
+        assert!(lines[3] == "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
+        assert!(lines[4] == "153 255 204 153 255 204 153 255 204 153 255 204 153");
+        assert!(lines[5] == "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204");
+        assert!(lines[6] == "153 255 204 153 255 204 153 255 204 153 255 204 153");
+    }
 
     #[test]
     fn ppm_termintated_with_newline() {
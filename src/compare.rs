@@ -0,0 +1,168 @@
+use super::canvas::Canvas;
+use super::color::{color, Color, BLACK};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum CompareError {
+    SizeMismatch((usize, usize), (usize, usize)),
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompareError::SizeMismatch((w1, h1), (w2, h2)) => write!(
+                f,
+                "images have different sizes: {}x{} and {}x{}",
+                w1, h1, w2, h2
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CompareError {}
+
+// differences between two images. errors are measured per channel, with 1.0
+// as the peak value for psnr, so identical images have infinite psnr.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub difference: Canvas,
+    pub rmse: f64,
+    pub psnr: f64,
+    pub max_error: f64,
+    pub differing_pixels: usize,
+}
+
+pub fn compare(a: &Canvas, b: &Canvas) -> Result<Comparison, CompareError> {
+    if a.width != b.width || a.height != b.height {
+        return Err(CompareError::SizeMismatch(
+            (a.width, a.height),
+            (b.width, b.height),
+        ));
+    }
+
+    let mut difference = Canvas::new(a.width, a.height);
+    let mut squared_error = 0.0;
+    let mut max_error: f64 = 0.0;
+    let mut differing_pixels = 0;
+
+    for (i, (x, y)) in a.pixels.iter().zip(b.pixels.iter()).enumerate() {
+        let diff = Color((x.0 - y.0).abs(), (x.1 - y.1).abs(), (x.2 - y.2).abs());
+        let pixel_error = diff.0.max(diff.1).max(diff.2);
+
+        squared_error += diff.0 * diff.0 + diff.1 * diff.1 + diff.2 * diff.2;
+        max_error = max_error.max(pixel_error);
+        if pixel_error > 0.0 {
+            differing_pixels += 1;
+        }
+        difference[i] = diff;
+    }
+
+    let samples = (a.pixels.len() * 3).max(1) as f64;
+    let rmse = (squared_error / samples).sqrt();
+    let psnr = match rmse == 0.0 {
+        true => f64::INFINITY,
+        false => 20.0 * (1.0 / rmse).log10(),
+    };
+
+    Ok(Comparison {
+        difference,
+        rmse,
+        psnr,
+        max_error,
+        differing_pixels,
+    })
+}
+
+// blue through green and yellow to red as t goes from 0 to 1
+fn heat(t: f64) -> Color {
+    let stops = [
+        color(0, 0, 1),
+        color(0, 1, 0),
+        color(1, 1, 0),
+        color(1, 0, 0),
+    ];
+    let scaled = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let i = (scaled.floor() as usize).min(stops.len() - 2);
+    let f = scaled - i as f64;
+    stops[i] * (1.0 - f) + stops[i + 1] * f
+}
+
+impl Comparison {
+    // whether no channel of any pixel differs by more than the tolerance
+    pub fn within(&self, tolerance: f64) -> bool {
+        self.max_error <= tolerance
+    }
+
+    // each pixel's largest channel error as a heat colour, scaled so that
+    // the worst pixel is red. identical pixels are black.
+    pub fn heatmap(&self) -> Canvas {
+        let mut heatmap = Canvas::new(self.difference.width, self.difference.height);
+        for (i, diff) in self.difference.pixels.iter().enumerate() {
+            let error = diff.0.max(diff.1).max(diff.2);
+            heatmap[i] = match error > 0.0 {
+                true => heat(error / self.max_error),
+                false => BLACK,
+            };
+        }
+        heatmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+
+    #[test]
+    fn comparing_identical_images() {
+        let mut a = Canvas::new(4, 3);
+        a.write_pixel(1, 2, color(0.2, 0.4, 0.6));
+
+        let comparison = compare(&a, &a.clone()).unwrap();
+
+        assert!(comparison.rmse == 0.0);
+        assert!(comparison.psnr == f64::INFINITY);
+        assert!(comparison.max_error == 0.0);
+        assert!(comparison.differing_pixels == 0);
+        assert!(comparison.heatmap() == Canvas::new(4, 3));
+    }
+
+    #[test]
+    fn comparing_images_with_one_different_pixel() {
+        let a = Canvas::new(2, 2);
+        let mut b = Canvas::new(2, 2);
+        b.write_pixel(1, 0, color(0.4, 0, -0.2));
+
+        let comparison = compare(&a, &b).unwrap();
+
+        assert!(comparison.difference.get_pixel(1, 0) == &color(0.4, 0, 0.2));
+        assert!((comparison.rmse - (0.2_f64 / 12.0).sqrt()).abs() < 1e-9);
+        assert!((comparison.psnr - 17.78151).abs() < 0.0001);
+        assert!(comparison.max_error == 0.4);
+        assert!(comparison.differing_pixels == 1);
+        assert!(comparison.within(0.4));
+        assert!(!comparison.within(0.3));
+    }
+
+    #[test]
+    fn comparing_images_of_different_sizes() {
+        let a = Canvas::new(2, 2);
+        let b = Canvas::new(3, 2);
+
+        assert!(compare(&a, &b) == Err(CompareError::SizeMismatch((2, 2), (3, 2))));
+    }
+
+    #[test]
+    fn heatmap_scales_errors_to_the_worst_pixel() {
+        let a = Canvas::new(3, 1);
+        let mut b = Canvas::new(3, 1);
+        b.write_pixel(0, 0, color(0.1, 0, 0));
+        b.write_pixel(2, 0, WHITE);
+
+        let heatmap = compare(&a, &b).unwrap().heatmap();
+
+        assert!(heatmap.get_pixel(0, 0) == &color(0, 0.3, 0.7));
+        assert!(heatmap.get_pixel(1, 0) == &BLACK);
+        assert!(heatmap.get_pixel(2, 0) == &color(1, 0, 0));
+    }
+}
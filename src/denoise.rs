@@ -0,0 +1,221 @@
+use super::camera::RenderResult;
+use super::canvas::Canvas;
+use super::color::{Color, BLACK};
+
+// a joint bilateral filter: each pixel becomes a weighted average of its
+// neighbours, where neighbours that differ in colour, albedo or normal count
+// for less, so that edges survive while noise on flat areas is smoothed out
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Denoiser {
+    pub radius: usize,
+    pub spatial_sigma: f64,
+    pub color_sigma: f64,
+    pub albedo_sigma: f64,
+    pub normal_sigma: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Denoiser {
+        Denoiser {
+            radius: 3,
+            spatial_sigma: 2.0,
+            color_sigma: 0.3,
+            albedo_sigma: 0.1,
+            normal_sigma: 0.2,
+        }
+    }
+}
+
+fn distance_squared(a: &Color, b: &Color) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)
+}
+
+// a sigma of zero is the limit of an ever narrower bell: only identical
+// values get any weight
+fn gaussian(distance_squared: f64, sigma: f64) -> f64 {
+    if sigma == 0.0 {
+        return if distance_squared == 0.0 { 1.0 } else { 0.0 };
+    }
+    (-distance_squared / (2.0 * sigma * sigma)).exp()
+}
+
+impl Denoiser {
+    pub fn new(radius: usize) -> Denoiser {
+        Denoiser {
+            radius,
+            spatial_sigma: radius.max(1) as f64 / 1.5,
+            ..Denoiser::default()
+        }
+    }
+
+    // the guide buffers must be the same size as the image; a mismatch is a
+    // programming error rather than bad input, so it panics
+    pub fn denoise(
+        &self,
+        image: &Canvas,
+        albedo: Option<&Canvas>,
+        normal: Option<&Canvas>,
+    ) -> Canvas {
+        for guide in albedo.iter().chain(normal.iter()) {
+            assert!(
+                guide.width == image.width && guide.height == image.height,
+                "guide buffer is {}x{} but the image is {}x{}",
+                guide.width,
+                guide.height,
+                image.width,
+                image.height
+            );
+        }
+
+        let radius = self.radius as isize;
+        let mut output = Canvas::new(image.width, image.height);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let centre = x + y * image.width;
+                let mut sum = BLACK;
+                let mut total = 0.0;
+
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let (nx, ny) = (x as isize + dx, y as isize + dy);
+                        if nx < 0
+                            || ny < 0
+                            || nx >= image.width as isize
+                            || ny >= image.height as isize
+                        {
+                            continue;
+                        }
+                        let neighbour = nx as usize + ny as usize * image.width;
+
+                        let mut weight = gaussian((dx * dx + dy * dy) as f64, self.spatial_sigma)
+                            * gaussian(
+                                distance_squared(&image[centre], &image[neighbour]),
+                                self.color_sigma,
+                            );
+                        if let Some(albedo) = albedo {
+                            weight *= gaussian(
+                                distance_squared(&albedo[centre], &albedo[neighbour]),
+                                self.albedo_sigma,
+                            );
+                        }
+                        if let Some(normal) = normal {
+                            weight *= gaussian(
+                                distance_squared(&normal[centre], &normal[neighbour]),
+                                self.normal_sigma,
+                            );
+                        }
+
+                        sum = sum + image[neighbour] * weight;
+                        total += weight;
+                    }
+                }
+
+                // the centre pixel always has a weight of one
+                output[centre] = sum * (1.0 / total);
+            }
+        }
+
+        output
+    }
+
+    pub fn denoise_passes(&self, result: &RenderResult) -> Canvas {
+        self.denoise(&result.beauty, Some(&result.albedo), Some(&result.normal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{color, WHITE};
+
+    fn filled(width: usize, height: usize, c: Color) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for pixel in canvas.pixels.iter_mut() {
+            *pixel = c;
+        }
+        canvas
+    }
+
+    #[test]
+    fn denoising_a_flat_image_leaves_it_unchanged() {
+        let image = filled(6, 4, color(0.2, 0.4, 0.6));
+
+        assert!(Denoiser::default().denoise(&image, None, None) == image);
+    }
+
+    #[test]
+    fn denoising_smooths_out_an_isolated_speck() {
+        let mut image = filled(7, 7, color(0.5, 0.5, 0.5));
+        image.write_pixel(3, 3, color(0.6, 0.6, 0.6));
+
+        let denoised = Denoiser::default().denoise(&image, None, None);
+
+        let speck = denoised.get_pixel(3, 3).0;
+        assert!(speck > 0.5 && speck < 0.52);
+    }
+
+    #[test]
+    fn denoising_keeps_edges_in_the_albedo() {
+        let mut image = Canvas::new(8, 1);
+        let mut albedo = Canvas::new(8, 1);
+        for x in 4..8 {
+            image.write_pixel(x, 0, color(0.2, 0.2, 0.2));
+            albedo.write_pixel(x, 0, WHITE);
+        }
+        let denoiser = Denoiser {
+            color_sigma: 10.0,
+            ..Denoiser::default()
+        };
+
+        let blurred = denoiser.denoise(&image, None, None);
+        let guided = denoiser.denoise(&image, Some(&albedo), None);
+
+        assert!(blurred.get_pixel(3, 0).0 > 0.01);
+        assert!(guided.get_pixel(3, 0).0 < 0.0001);
+        assert!(guided.get_pixel(4, 0) == &color(0.2, 0.2, 0.2));
+    }
+
+    #[test]
+    fn denoising_keeps_edges_in_the_normals() {
+        let mut image = Canvas::new(8, 1);
+        let mut normal = filled(8, 1, color(0, 0, -1));
+        for x in 4..8 {
+            image.write_pixel(x, 0, color(0.2, 0.2, 0.2));
+            normal.write_pixel(x, 0, color(0, 1, 0));
+        }
+        let denoiser = Denoiser {
+            color_sigma: 10.0,
+            ..Denoiser::default()
+        };
+
+        let guided = denoiser.denoise(&image, None, Some(&normal));
+
+        assert!(guided.get_pixel(3, 0).0 < 0.0001);
+    }
+
+    #[test]
+    #[should_panic(expected = "guide buffer is 2x2 but the image is 4x4")]
+    fn denoising_needs_guides_of_the_same_size() {
+        let image = Canvas::new(4, 4);
+        let albedo = Canvas::new(2, 2);
+
+        Denoiser::default().denoise(&image, Some(&albedo), None);
+    }
+
+    #[test]
+    fn zero_sigmas_only_average_identical_pixels() {
+        let mut image = filled(3, 1, color(0.5, 0.5, 0.5));
+        image.write_pixel(2, 0, WHITE);
+        let spatial = Denoiser {
+            spatial_sigma: 0.0,
+            ..Denoiser::default()
+        };
+        let colour = Denoiser {
+            color_sigma: 0.0,
+            ..Denoiser::default()
+        };
+
+        assert!(spatial.denoise(&image, None, None) == image);
+        assert!(colour.denoise(&image, None, None) == image);
+    }
+}
@@ -0,0 +1,336 @@
+use super::ImageError;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io::{self, BufWriter, Write};
+
+// values below this are stored as black
+const MIN_RGBE: f64 = 1e-32;
+// the largest value rgbe can hold, 255 * 2^(127 - 8)
+const MAX_RGBE: f64 = 1.694_765_694_625_767_7e38;
+
+// shared exponent encoding; negative channels can't be represented and
+// are stored as zero. nan is stored as zero too, and anything too bright,
+// infinity included, as the largest value.
+fn to_rgbe(color: &Color) -> [u8; 4] {
+    let channel = |v: f64| match v.is_nan() {
+        true => 0.0,
+        false => v.clamp(0.0, MAX_RGBE),
+    };
+    let (r, g, b) = (channel(color.0), channel(color.1), channel(color.2));
+    let max = r.max(g).max(b);
+    if max < MIN_RGBE {
+        return [0, 0, 0, 0];
+    }
+
+    let mut exponent = max.log2().floor() as i32 + 1;
+    if max / 2_f64.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale = 256.0 / 2_f64.powi(exponent);
+    let byte = |v: f64| (v * scale).min(255.0) as u8;
+
+    [
+        byte(r),
+        byte(g),
+        byte(b),
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color(0.0, 0.0, 0.0);
+    }
+
+    let f = 2_f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color(
+        (rgbe[0] as f64 + 0.5) * f,
+        (rgbe[1] as f64 + 0.5) * f,
+        (rgbe[2] as f64 + 0.5) * f,
+    )
+}
+
+// reads a single header line, without its newline
+fn line<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str, ImageError> {
+    let start = *pos;
+    let end = data[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map(|i| start + i)
+        .ok_or(ImageError::UnexpectedEof)?;
+    *pos = end + 1;
+
+    std::str::from_utf8(&data[start..end])
+        .map_err(|_| ImageError::MalformedHeader("header is not text".into()))
+}
+
+// decodes one scanline, which is either flat rgbe or run length encoded
+// one channel at a time
+fn scanline(data: &[u8], pos: &mut usize, width: usize) -> Result<Vec<[u8; 4]>, ImageError> {
+    let rest = &data[*pos..];
+    let is_rle = (8..0x8000).contains(&width)
+        && rest.len() >= 4
+        && rest[0] == 2
+        && rest[1] == 2
+        && rest[2] & 0x80 == 0;
+
+    if !is_rle {
+        if rest.len() < width * 4 {
+            return Err(ImageError::UnexpectedEof);
+        }
+        *pos += width * 4;
+        return Ok(rest[..width * 4]
+            .chunks_exact(4)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .collect());
+    }
+
+    if ((rest[2] as usize) << 8 | rest[3] as usize) != width {
+        return Err(ImageError::InvalidData("scanline width mismatch".into()));
+    }
+    *pos += 4;
+
+    let mut pixels = vec![[0; 4]; width];
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.get(*pos).ok_or(ImageError::UnexpectedEof)? as usize;
+            *pos += 1;
+            if count > 128 {
+                let count = count - 128;
+                let value = *data.get(*pos).ok_or(ImageError::UnexpectedEof)?;
+                *pos += 1;
+                if x + count > width {
+                    return Err(ImageError::InvalidData("run overflows scanline".into()));
+                }
+                for pixel in &mut pixels[x..x + count] {
+                    pixel[channel] = value;
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(ImageError::InvalidData("bad run length".into()));
+                }
+                let values = data
+                    .get(*pos..*pos + count)
+                    .ok_or(ImageError::UnexpectedEof)?;
+                *pos += count;
+                for (pixel, value) in pixels[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = *value;
+                }
+                x += count;
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+impl Canvas {
+    // flat (uncompressed) radiance rgbe, top row first
+    pub fn write_hdr<W: Write>(&self, out: W) -> io::Result<()> {
+        let mut out = BufWriter::new(out);
+        write!(
+            out,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )?;
+
+        for color in self.pixels.iter() {
+            out.write_all(&to_rgbe(color))?;
+        }
+
+        out.flush()
+    }
+
+    pub fn from_hdr(data: &[u8]) -> Result<Canvas, ImageError> {
+        let mut pos = 0;
+
+        let magic = line(data, &mut pos)?;
+        if !magic.starts_with("#?") {
+            return Err(ImageError::MalformedHeader(format!(
+                "unsupported magic number '{}'",
+                magic
+            )));
+        }
+
+        loop {
+            let header = line(data, &mut pos)?;
+            if header.is_empty() {
+                break;
+            }
+            if let Some(format) = header.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(ImageError::MalformedHeader(format!(
+                        "unsupported format '{}'",
+                        format
+                    )));
+                }
+            }
+        }
+
+        let resolution = line(data, &mut pos)?;
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (height.parse().ok(), width.parse().ok()),
+            _ => (None, None),
+        };
+        let (height, width): (usize, usize) = match (height, width) {
+            (Some(height), Some(width)) => (height, width),
+            _ => {
+                return Err(ImageError::MalformedHeader(format!(
+                    "unsupported resolution '{}'",
+                    resolution
+                )))
+            }
+        };
+
+        if width == 0 || height == 0 {
+            return Err(ImageError::MalformedHeader(format!(
+                "invalid size {}x{}",
+                width, height
+            )));
+        }
+
+        // run length encoded scanlines can be much smaller than flat ones,
+        // but never smaller than a run per 127 pixels in each channel
+        let too_large = || ImageError::InvalidData("image is too large".into());
+        width.checked_mul(height).ok_or_else(too_large)?;
+        let min_scanline = match (8..0x8000).contains(&width) {
+            true => 4 + 8 * width.div_ceil(127),
+            false => width.checked_mul(4).ok_or_else(too_large)?,
+        };
+        let min_payload = height.checked_mul(min_scanline).ok_or_else(too_large)?;
+        if data.len() - pos < min_payload {
+            return Err(ImageError::UnexpectedEof);
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for (x, rgbe) in scanline(data, &mut pos, width)?.into_iter().enumerate() {
+                canvas.write_pixel(x, y, from_rgbe(rgbe));
+            }
+        }
+
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+
+    fn close(a: &Color, b: &Color) -> bool {
+        let channels = [(a.0, b.0), (a.1, b.1), (a.2, b.2)];
+        let max = b.0.max(b.1).max(b.2);
+        channels.iter().all(|(x, y)| (x - y).abs() <= max / 128.0)
+    }
+
+    #[test]
+    fn rgbe_shares_the_largest_channels_exponent() {
+        assert!(to_rgbe(&color(1, 0.5, 0.25)) == [128, 64, 32, 129]);
+        assert!(to_rgbe(&color(0, 0, 0)) == [0, 0, 0, 0]);
+        assert!(to_rgbe(&color(-1, 2, 0)) == [0, 128, 0, 130]);
+    }
+
+    #[test]
+    fn rgbe_saturates_values_it_cant_hold() {
+        let inf = f64::INFINITY;
+        assert!(to_rgbe(&color(inf, 0, 0)) == [255, 0, 0, 255]);
+        assert!(to_rgbe(&color(inf, inf, 1e300)) == [255, 255, 255, 255]);
+        assert!(to_rgbe(&color(f64::NAN, 1, f64::NEG_INFINITY)) == [0, 128, 0, 129]);
+        assert!(to_rgbe(&color(f64::NAN, f64::NAN, f64::NAN)) == [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rgbe_decodes_to_the_middle_of_each_step() {
+        assert!(from_rgbe([128, 64, 32, 129]) == color(1.00390625, 0.50390625, 0.25390625));
+        assert!(from_rgbe([10, 20, 30, 0]) == color(0, 0, 0));
+    }
+
+    #[test]
+    fn hdr_has_a_radiance_header() {
+        let canvas = Canvas::new(4, 3);
+        let mut hdr = vec![];
+        canvas.write_hdr(&mut hdr).unwrap();
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 3 +X 4\n";
+        assert!(hdr.starts_with(header));
+        assert!(hdr.len() == header.len() + 4 * 3 * 4);
+    }
+
+    #[test]
+    fn hdr_round_trips_values_above_one() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, color(12.5, 3.0, 0.125));
+        canvas.write_pixel(2, 1, color(0.01, 0.02, 0.03));
+        canvas.write_pixel(1, 1, color(1500, 0.5, 1));
+
+        let mut hdr = vec![];
+        canvas.write_hdr(&mut hdr).unwrap();
+        let read = Canvas::from_hdr(&hdr).unwrap();
+
+        assert!(read.width == 3 && read.height == 2);
+        for (a, b) in read.pixels.iter().zip(canvas.pixels.iter()) {
+            assert!(close(a, b));
+        }
+    }
+
+    #[test]
+    fn reading_a_run_length_encoded_hdr() {
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        hdr.extend_from_slice(&[2, 2, 0, 8]);
+        // red: a run of eight
+        hdr.extend_from_slice(&[128 + 8, 128]);
+        // green: eight literals
+        hdr.extend_from_slice(&[8, 0, 0, 0, 0, 64, 64, 64, 64]);
+        // blue: two runs
+        hdr.extend_from_slice(&[128 + 4, 0, 128 + 4, 128]);
+        // exponent
+        hdr.extend_from_slice(&[128 + 8, 129]);
+
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+
+        assert!(close(canvas.get_pixel(0, 0), &color(1, 0, 0)));
+        assert!(close(canvas.get_pixel(7, 0), &color(1, 0.5, 1)));
+    }
+
+    #[test]
+    fn hdr_parsing_rejects_other_formats() {
+        let hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0";
+
+        match Canvas::from_hdr(hdr) {
+            Err(ImageError::MalformedHeader(_)) => {}
+            _ => panic!("expected a malformed header"),
+        }
+    }
+
+    #[test]
+    fn hdr_parsing_reports_missing_pixel_data() {
+        let hdr = b"#?RADIANCE\n\n-Y 2 +X 1\n\x80\x80\x80\x81";
+
+        assert!(Canvas::from_hdr(hdr) == Err(ImageError::UnexpectedEof));
+    }
+
+    #[test]
+    fn hdr_parsing_rejects_an_empty_image() {
+        let hdr = b"#?RADIANCE\n\n-Y 1000000 +X 0\n";
+
+        assert!(
+            Canvas::from_hdr(hdr)
+                == Err(ImageError::MalformedHeader("invalid size 0x1000000".into()))
+        );
+    }
+
+    #[test]
+    fn hdr_parsing_checks_the_size_before_allocating() {
+        let huge = b"#?RADIANCE\n\n-Y 100000 +X 100000\n\x80\x80\x80\x81";
+        let overflowing = format!("#?RADIANCE\n\n-Y {} +X {}\n", usize::MAX, usize::MAX);
+
+        assert!(Canvas::from_hdr(huge) == Err(ImageError::UnexpectedEof));
+        assert!(
+            Canvas::from_hdr(overflowing.as_bytes())
+                == Err(ImageError::InvalidData("image is too large".into()))
+        );
+    }
+}
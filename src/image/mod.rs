@@ -0,0 +1,150 @@
+mod hdr;
+mod pfm;
+mod png;
+mod ppm;
+
+pub use png::*;
+pub use ppm::PpmFormat;
+
+use crate::canvas::Canvas;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, PartialEq)]
+pub enum ImageError {
+    MalformedHeader(String),
+    InvalidData(String),
+    UnexpectedEof,
+    Unsupported(String),
+    Io(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::MalformedHeader(msg) => write!(f, "malformed image header: {}", msg),
+            ImageError::InvalidData(msg) => write!(f, "invalid image data: {}", msg),
+            ImageError::UnexpectedEof => write!(f, "image data ended unexpectedly"),
+            ImageError::Unsupported(msg) => write!(f, "unsupported image: {}", msg),
+            ImageError::Io(msg) => write!(f, "couldn't read image: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl Canvas {
+    // picks a reader from the file's magic number
+    pub fn read_image(data: &[u8]) -> Result<Canvas, ImageError> {
+        match data {
+            [b'P', b'3', ..] | [b'P', b'6', ..] => Canvas::from_ppm(data),
+            [b'P', b'F', ..] | [b'P', b'f', ..] => Canvas::from_pfm(data),
+            [b'#', b'?', ..] => Canvas::from_hdr(data),
+            [0x89, b'P', b'N', b'G', ..] => Err(ImageError::Unsupported(
+                "png files can only be written".into(),
+            )),
+            [] => Err(ImageError::UnexpectedEof),
+            _ => Err(ImageError::Unsupported("unknown file format".into())),
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Canvas, ImageError> {
+        let path = path.as_ref();
+        let data =
+            fs::read(path).map_err(|err| ImageError::Io(format!("{}: {}", path.display(), err)))?;
+        Canvas::read_image(&data)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+// transfer function applied to linear values before they are quantised
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gamma {
+    Linear,
+    Srgb,
+    Power(f64),
+}
+
+impl Gamma {
+    pub fn encode(&self, v: f64) -> f64 {
+        match self {
+            Gamma::Linear => v,
+            Gamma::Srgb => match v <= 0.003_130_8 {
+                true => v * 12.92,
+                false => 1.055 * v.powf(1.0 / 2.4) - 0.055,
+            },
+            Gamma::Power(g) => v.max(0.0).powf(1.0 / g),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+
+    #[test]
+    fn reading_an_image_detects_its_format() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(1, 0, color(0.2, 0.4, 0.6));
+
+        let ascii = canvas.to_ppm().into_bytes();
+        let mut binary = vec![];
+        canvas.write_ppm(&mut binary, PpmFormat::Binary).unwrap();
+        let mut pfm = vec![];
+        canvas.write_pfm(&mut pfm).unwrap();
+
+        for data in [ascii, binary, pfm].iter() {
+            assert!(Canvas::read_image(data).unwrap() == canvas);
+        }
+
+        let mut hdr = vec![];
+        canvas.write_hdr(&mut hdr).unwrap();
+        assert!(Canvas::read_image(&hdr).unwrap().width == 2);
+    }
+
+    #[test]
+    fn reading_an_unknown_image_format() {
+        let png = Canvas::new(1, 1).to_png(PngOptions::default());
+
+        match Canvas::read_image(&png) {
+            Err(ImageError::Unsupported(_)) => {}
+            _ => panic!("expected an unsupported image"),
+        }
+        match Canvas::read_image(b"GIF89a") {
+            Err(ImageError::Unsupported(_)) => {}
+            _ => panic!("expected an unsupported image"),
+        }
+    }
+
+    #[test]
+    fn opening_a_missing_file() {
+        match Canvas::open("no/such/image.ppm") {
+            Err(ImageError::Io(msg)) => assert!(msg.starts_with("no/such/image.ppm")),
+            _ => panic!("expected an io error"),
+        }
+    }
+
+    #[test]
+    fn linear_gamma_leaves_values_alone() {
+        assert!(Gamma::Linear.encode(0.25) == 0.25);
+    }
+
+    #[test]
+    fn srgb_gamma_has_a_linear_toe() {
+        assert!((Gamma::Srgb.encode(0.002) - 0.02584).abs() < 0.0001);
+        assert!((Gamma::Srgb.encode(0.5) - 0.73536).abs() < 0.0001);
+        assert!((Gamma::Srgb.encode(1.0) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn power_gamma_raises_to_the_reciprocal() {
+        assert!((Gamma::Power(2.0).encode(0.25) - 0.5).abs() < 0.0001);
+    }
+}
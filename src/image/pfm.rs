@@ -0,0 +1,174 @@
+use super::ppm::Tokens;
+use super::ImageError;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::io::{self, BufWriter, Write};
+
+impl Canvas {
+    // colour PFM, little endian, rows stored bottom to top
+    pub fn write_pfm<W: Write>(&self, out: W) -> io::Result<()> {
+        let mut out = BufWriter::new(out);
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+
+        for row in self.pixels.chunks(self.width.max(1)).rev() {
+            for color in row {
+                for channel in [color.0, color.1, color.2].iter() {
+                    out.write_all(&(*channel as f32).to_le_bytes())?;
+                }
+            }
+        }
+
+        out.flush()
+    }
+
+    pub fn from_pfm(data: &[u8]) -> Result<Canvas, ImageError> {
+        let mut tokens = Tokens::new(data);
+
+        let channels = match tokens.next() {
+            Some("PF") => 3,
+            Some("Pf") => 1,
+            Some(magic) => {
+                return Err(ImageError::MalformedHeader(format!(
+                    "unsupported magic number '{}'",
+                    magic
+                )))
+            }
+            None => return Err(ImageError::MalformedHeader("missing magic number".into())),
+        };
+
+        let width: usize = tokens.header("width")?;
+        let height: usize = tokens.header("height")?;
+        let scale: f32 = tokens.header("scale")?;
+        if width == 0 || height == 0 {
+            return Err(ImageError::MalformedHeader(format!(
+                "invalid size {}x{}",
+                width, height
+            )));
+        }
+        if scale == 0.0 || !scale.is_finite() {
+            return Err(ImageError::MalformedHeader(format!(
+                "invalid scale {}",
+                scale
+            )));
+        }
+        let little_endian = scale < 0.0;
+
+        let raster = tokens.raster();
+        let size = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels * 4))
+            .ok_or(ImageError::InvalidData("image is too large".into()))?;
+        if raster.len() < size {
+            return Err(ImageError::UnexpectedEof);
+        }
+
+        let mut samples = raster.chunks_exact(4).map(|bytes| {
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            match little_endian {
+                true => f32::from_le_bytes(bytes) as f64,
+                false => f32::from_be_bytes(bytes) as f64,
+            }
+        });
+
+        let mut canvas = Canvas::new(width, height);
+        for y in (0..height).rev() {
+            for x in 0..width {
+                let color = match channels {
+                    3 => Color(
+                        samples.next().unwrap(),
+                        samples.next().unwrap(),
+                        samples.next().unwrap(),
+                    ),
+                    _ => {
+                        let v = samples.next().unwrap();
+                        Color(v, v, v)
+                    }
+                };
+                canvas.write_pixel(x, y, color);
+            }
+        }
+
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+
+    #[test]
+    fn pfm_has_a_little_endian_header() {
+        let canvas = Canvas::new(3, 2);
+        let mut pfm = vec![];
+        canvas.write_pfm(&mut pfm).unwrap();
+
+        assert!(pfm.starts_with(b"PF\n3 2\n-1.0\n"));
+        assert!(pfm.len() == 12 + 3 * 2 * 3 * 4);
+    }
+
+    #[test]
+    fn pfm_stores_the_bottom_row_first() {
+        let mut canvas = Canvas::new(1, 2);
+        canvas.write_pixel(0, 1, color(2.5, 0, 0));
+        let mut pfm = vec![];
+        canvas.write_pfm(&mut pfm).unwrap();
+
+        assert!(pfm[12..16] == 2.5_f32.to_le_bytes());
+    }
+
+    #[test]
+    fn pfm_round_trips_values_outside_the_unit_range() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, color(12.5, -0.25, 0.125));
+        canvas.write_pixel(2, 1, color(1000, 0.5, 3));
+
+        let mut pfm = vec![];
+        canvas.write_pfm(&mut pfm).unwrap();
+
+        assert!(Canvas::from_pfm(&pfm).unwrap() == canvas);
+    }
+
+    #[test]
+    fn reading_a_big_endian_greyscale_pfm() {
+        let mut pfm = b"Pf\n2 1\n1.0\n".to_vec();
+        pfm.extend_from_slice(&0.25_f32.to_be_bytes());
+        pfm.extend_from_slice(&4.0_f32.to_be_bytes());
+        let canvas = Canvas::from_pfm(&pfm).unwrap();
+
+        assert!(canvas.get_pixel(0, 0) == &color(0.25, 0.25, 0.25));
+        assert!(canvas.get_pixel(1, 0) == &color(4, 4, 4));
+    }
+
+    #[test]
+    fn pfm_parsing_reports_missing_pixel_data() {
+        let pfm = b"PF\n2 2\n-1.0\n\0\0\0\0";
+
+        assert!(Canvas::from_pfm(pfm) == Err(ImageError::UnexpectedEof));
+    }
+
+    #[test]
+    fn pfm_parsing_rejects_an_empty_image() {
+        let pfm = format!("PF\n0 {}\n-1.0\n", usize::MAX);
+
+        assert!(
+            Canvas::from_pfm(pfm.as_bytes())
+                == Err(ImageError::MalformedHeader(format!(
+                    "invalid size 0x{}",
+                    usize::MAX
+                )))
+        );
+    }
+
+    #[test]
+    fn pfm_parsing_checks_the_size_before_allocating() {
+        let huge = b"PF\n100000 100000\n-1.0\n\0\0\0\0";
+        let overflowing = format!("PF\n{} {}\n-1.0\n", usize::MAX, usize::MAX);
+
+        assert!(Canvas::from_pfm(huge) == Err(ImageError::UnexpectedEof));
+        assert!(
+            Canvas::from_pfm(overflowing.as_bytes())
+                == Err(ImageError::InvalidData("image is too large".into()))
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use super::{BitDepth, Gamma};
+use crate::canvas::Canvas;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PngOptions {
+    pub bit_depth: BitDepth,
+    pub gamma: Gamma,
+}
+
+impl Default for PngOptions {
+    fn default() -> PngOptions {
+        PngOptions {
+            bit_depth: BitDepth::Eight,
+            gamma: Gamma::Linear,
+        }
+    }
+}
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+// the largest block deflate can store uncompressed
+const MAX_STORED_BLOCK: usize = 65535;
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff_u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK);
+    let mut out = Vec::with_capacity(data.len() + blocks.max(1) * 5 + 6);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    if data.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    for (i, block) in data.chunks(MAX_STORED_BLOCK).enumerate() {
+        let last = i + 1 == blocks;
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+impl Canvas {
+    pub fn to_png(&self, options: PngOptions) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        let depth = match options.bit_depth {
+            BitDepth::Eight => 8,
+            BitDepth::Sixteen => 16,
+        };
+        // truecolour, deflate, no filtering, not interlaced
+        header.extend_from_slice(&[depth, 2, 0, 0, 0]);
+
+        let bytes_per_sample = depth as usize / 8;
+        let mut raw = Vec::with_capacity(self.height * (1 + self.width * 3 * bytes_per_sample));
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            for color in row {
+                for channel in [color.0, color.1, color.2].iter() {
+                    let v = options.gamma.encode(channel.clamp(0.0, 1.0));
+                    match options.bit_depth {
+                        BitDepth::Eight => raw.push((v * 255.0).round() as u8),
+                        BitDepth::Sixteen => {
+                            raw.extend_from_slice(&((v * 65535.0).round() as u16).to_be_bytes())
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut png = SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        match options.gamma {
+            Gamma::Linear => {}
+            // perceptual rendering intent
+            Gamma::Srgb => write_chunk(&mut png, b"sRGB", &[0]),
+            Gamma::Power(g) => {
+                let value = (100_000.0 / g).round() as u32;
+                write_chunk(&mut png, b"gAMA", &value.to_be_bytes())
+            }
+        }
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn u32_at(bytes: &[u8], i: usize) -> u32 {
+        u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+    }
+
+    // walks the chunks of a png, returning their types and contents
+    fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut chunks = vec![];
+        let mut i = 8;
+        while i < png.len() {
+            let len = u32_at(png, i) as usize;
+            let kind = String::from_utf8(png[i + 4..i + 8].to_vec()).unwrap();
+            let data = png[i + 8..i + 8 + len].to_vec();
+            assert!(u32_at(png, i + 8 + len) == crc32(&png[i + 4..i + 8 + len]));
+            chunks.push((kind, data));
+            i += 12 + len;
+        }
+        chunks
+    }
+
+    // undoes `zlib_stored`
+    fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        let mut i = 2;
+        loop {
+            let last = zlib[i] & 1 == 1;
+            let len = u16::from_le_bytes([zlib[i + 1], zlib[i + 2]]) as usize;
+            out.extend_from_slice(&zlib[i + 5..i + 5 + len]);
+            i += 5 + len;
+            if last {
+                break;
+            }
+        }
+        assert!(u32_at(zlib, i) == adler32(&out));
+        out
+    }
+
+    #[test]
+    fn checksums_match_reference_values() {
+        assert!(crc32(b"IEND") == 0xae42_6082);
+        assert!(adler32(b"Wikipedia") == 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_starts_with_signature_and_header() {
+        let canvas = Canvas::new(10, 20);
+        let png = canvas.to_png(PngOptions::default());
+
+        assert!(png[..8] == SIGNATURE);
+        let chunks = chunks(&png);
+        assert!(chunks[0].0 == "IHDR");
+        assert!(u32_at(&chunks[0].1, 0) == 10);
+        assert!(u32_at(&chunks[0].1, 4) == 20);
+        assert!(chunks[0].1[8..] == [8, 2, 0, 0, 0]);
+        assert!(chunks.last().unwrap().0 == "IEND");
+    }
+
+    #[test]
+    fn png_pixel_data_is_clamped_rows() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color(1.5, 0.0, 0.0));
+        canvas.write_pixel(1, 1, Color(-0.5, 0.5, 1.0));
+
+        let png = canvas.to_png(PngOptions::default());
+        let chunks = chunks(&png);
+        let idat = &chunks.iter().find(|(kind, _)| kind == "IDAT").unwrap().1;
+
+        let raw = inflate_stored(idat);
+        assert!(raw == vec![0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 255]);
+    }
+
+    #[test]
+    fn sixteen_bit_png_uses_two_bytes_per_sample() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color(1.0, 0.5, 0.0));
+        let options = PngOptions {
+            bit_depth: BitDepth::Sixteen,
+            gamma: Gamma::Linear,
+        };
+
+        let chunks = chunks(&canvas.to_png(options));
+        assert!(chunks[0].1[8] == 16);
+        let raw = inflate_stored(&chunks.iter().find(|(k, _)| k == "IDAT").unwrap().1);
+        assert!(raw == vec![0, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn gamma_encoding_is_applied_and_recorded() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color(0.5, 0.5, 0.5));
+        let options = PngOptions {
+            bit_depth: BitDepth::Eight,
+            gamma: Gamma::Srgb,
+        };
+
+        let chunks = chunks(&canvas.to_png(options));
+        assert!(chunks[1].0 == "sRGB");
+        let raw = inflate_stored(&chunks.iter().find(|(k, _)| k == "IDAT").unwrap().1);
+        assert!(raw == vec![0, 188, 188, 188]);
+    }
+
+    #[test]
+    fn large_images_span_several_stored_blocks() {
+        let canvas = Canvas::new(200, 200);
+        let png = canvas.to_png(PngOptions::default());
+        let chunks = chunks(&png);
+
+        let raw = inflate_stored(&chunks.iter().find(|(k, _)| k == "IDAT").unwrap().1);
+        assert!(raw.len() == 200 * (1 + 200 * 3));
+    }
+}
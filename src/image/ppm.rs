@@ -0,0 +1,422 @@
+use super::ImageError;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use std::fmt::Write as _;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+// longest line allowed in the body of an ascii ppm
+const MAX_LINE_LENGTH: usize = 70;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PpmFormat {
+    Ascii,
+    Binary,
+}
+
+// walks the whitespace separated tokens of a netpbm file, skipping comments
+pub(super) struct Tokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Tokens<'a> {
+        Tokens { data, pos: 0 }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'#' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    pub(super) fn next(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.data.len() && !self.data[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+
+        match start == self.pos {
+            true => None,
+            false => std::str::from_utf8(&self.data[start..self.pos]).ok(),
+        }
+    }
+
+    pub(super) fn header<T: FromStr>(&mut self, name: &str) -> Result<T, ImageError> {
+        let token = self
+            .next()
+            .ok_or_else(|| ImageError::MalformedHeader(format!("missing {}", name)))?;
+        token
+            .parse()
+            .map_err(|_| ImageError::MalformedHeader(format!("invalid {} '{}'", name, token)))
+    }
+
+    // the raster of a binary file starts after the single whitespace byte
+    // which ends the header
+    pub(super) fn raster(self) -> &'a [u8] {
+        &self.data[(self.pos + 1).min(self.data.len())..]
+    }
+
+    fn sample(&mut self, max: u32) -> Result<f64, ImageError> {
+        let token = self.next().ok_or(ImageError::UnexpectedEof)?;
+        let value: u32 = token
+            .parse()
+            .map_err(|_| ImageError::InvalidData(format!("invalid sample '{}'", token)))?;
+        check_sample(value, max)
+    }
+}
+
+fn check_sample(value: u32, max: u32) -> Result<f64, ImageError> {
+    match value <= max {
+        true => Ok(value as f64 / max as f64),
+        false => Err(ImageError::InvalidData(format!(
+            "sample {} is above the maximum value {}",
+            value, max
+        ))),
+    }
+}
+
+fn to_byte(v: f64) -> u8 {
+    (v * 255.0).clamp(0.0, 255.0).round() as u8
+}
+
+// samples of a P6 raster are one byte each, or two big endian bytes when
+// the maximum value doesn't fit in one
+fn from_raster(raster: &[u8], width: usize, height: usize, max: u32) -> Result<Canvas, ImageError> {
+    let sample_size = if max < 256 { 1 } else { 2 };
+    if raster.len() < width * height * 3 * sample_size {
+        return Err(ImageError::UnexpectedEof);
+    }
+
+    let mut samples = raster.chunks_exact(sample_size).map(|bytes| {
+        let value = match bytes {
+            [b] => *b as u32,
+            _ => u16::from_be_bytes([bytes[0], bytes[1]]) as u32,
+        };
+        check_sample(value, max)
+    });
+
+    let mut canvas = Canvas::new(width, height);
+    for i in 0..width * height {
+        canvas[i] = Color(
+            samples.next().unwrap()?,
+            samples.next().unwrap()?,
+            samples.next().unwrap()?,
+        );
+    }
+
+    Ok(canvas)
+}
+
+impl Canvas {
+    pub fn write_ppm<W: Write>(&self, out: W, format: PpmFormat) -> io::Result<()> {
+        let mut out = BufWriter::new(out);
+        let magic = match format {
+            PpmFormat::Ascii => "P3",
+            PpmFormat::Binary => "P6",
+        };
+        write!(out, "{}\n{} {}\n255\n", magic, self.width, self.height)?;
+
+        let mut line = String::new();
+        let mut bytes = Vec::with_capacity(self.width * 3);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            bytes.clear();
+            for color in row {
+                bytes.extend_from_slice(&[to_byte(color.0), to_byte(color.1), to_byte(color.2)]);
+            }
+
+            match format {
+                PpmFormat::Binary => out.write_all(&bytes)?,
+                PpmFormat::Ascii => {
+                    line.clear();
+                    let mut line_start = 0;
+                    for byte in bytes.iter() {
+                        let digits = match byte {
+                            0..=9 => 1,
+                            10..=99 => 2,
+                            _ => 3,
+                        };
+                        if line.len() > line_start {
+                            if line.len() - line_start + 1 + digits > MAX_LINE_LENGTH {
+                                line.push('\n');
+                                line_start = line.len();
+                            } else {
+                                line.push(' ');
+                            }
+                        }
+                        // writing to a String can't fail
+                        write!(line, "{}", byte).unwrap();
+                    }
+                    line.push('\n');
+                    out.write_all(line.as_bytes())?;
+                }
+            }
+        }
+
+        out.flush()
+    }
+
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, ImageError> {
+        let mut tokens = Tokens::new(data);
+
+        let binary = match tokens.next() {
+            Some("P3") => false,
+            Some("P6") => true,
+            Some(magic) => {
+                return Err(ImageError::MalformedHeader(format!(
+                    "unsupported magic number '{}'",
+                    magic
+                )))
+            }
+            None => return Err(ImageError::MalformedHeader("missing magic number".into())),
+        };
+
+        let width: usize = tokens.header("width")?;
+        let height: usize = tokens.header("height")?;
+        let max: u32 = tokens.header("maximum value")?;
+        if max == 0 || max > 65535 {
+            return Err(ImageError::MalformedHeader(format!(
+                "maximum value {} out of range",
+                max
+            )));
+        }
+
+        if width == 0 || height == 0 {
+            return Err(ImageError::MalformedHeader(format!(
+                "invalid size {}x{}",
+                width, height
+            )));
+        }
+        // every sample takes at least a byte, so there's no point allocating
+        // more than the file could fill
+        match width.checked_mul(height).and_then(|n| n.checked_mul(3)) {
+            Some(samples) if samples <= data.len() => {}
+            _ => return Err(ImageError::UnexpectedEof),
+        }
+
+        if binary {
+            return from_raster(tokens.raster(), width, height, max);
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for i in 0..width * height {
+            let r = tokens.sample(max)?;
+            let g = tokens.sample(max)?;
+            let b = tokens.sample(max)?;
+            canvas[i] = Color(r, g, b);
+        }
+
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+
+    #[test]
+    fn binary_ppm_has_a_p6_header() {
+        let canvas = Canvas::new(10, 20);
+        let mut ppm = vec![];
+        canvas.write_ppm(&mut ppm, PpmFormat::Binary).unwrap();
+
+        assert!(ppm.starts_with(b"P6\n10 20\n255\n"));
+        assert!(ppm.len() == 13 + 10 * 20 * 3);
+    }
+
+    #[test]
+    fn binary_ppm_pixel_data_is_clamped_bytes() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, color(1.5, 0, 0));
+        canvas.write_pixel(1, 1, color(-0.5, 0.5, 1));
+
+        let mut ppm = vec![];
+        canvas.write_ppm(&mut ppm, PpmFormat::Binary).unwrap();
+
+        assert!(ppm[11..] == [255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 255]);
+    }
+
+    #[test]
+    fn ascii_ppm_streams_the_same_output_as_to_ppm() {
+        let mut canvas = Canvas::new(30, 3);
+        canvas.write_pixel(7, 1, color(0.2, 0.4, 0.6));
+
+        let mut ppm = vec![];
+        canvas.write_ppm(&mut ppm, PpmFormat::Ascii).unwrap();
+
+        assert!(ppm == canvas.to_ppm().into_bytes());
+        assert!(ppm
+            .split(|b| *b == b'\n')
+            .all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+
+    #[test]
+    fn binary_ppm_round_trips_through_the_reader() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, color(1, 0, 0));
+        canvas.write_pixel(2, 1, color(0.2, 0.4, 0.6));
+
+        let mut ppm = vec![];
+        canvas.write_ppm(&mut ppm, PpmFormat::Binary).unwrap();
+
+        assert!(Canvas::from_ppm(&ppm).unwrap() == canvas);
+    }
+
+    #[test]
+    fn reading_a_sixteen_bit_binary_ppm() {
+        let mut ppm = b"P6\n# comment\n1 1\n65535\n".to_vec();
+        ppm.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+
+        assert!(canvas.get_pixel(0, 0) == &color(1, 0.50001, 0));
+    }
+
+    #[test]
+    fn binary_ppm_parsing_reports_missing_pixel_data() {
+        let ppm = b"P6\n2 1\n255\n\xff\xff\xff";
+
+        assert!(Canvas::from_ppm(ppm) == Err(ImageError::UnexpectedEof));
+    }
+
+    #[test]
+    fn reading_a_file_with_the_wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0\n";
+
+        assert!(Canvas::from_ppm(ppm.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn reading_a_ppm_returns_a_canvas_of_the_right_size() {
+        let ppm = "P3\n10 2\n255\n".to_string() + &"0 0 0 ".repeat(20);
+        let canvas = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+
+        assert!(canvas.width == 10);
+        assert!(canvas.height == 2);
+    }
+
+    #[test]
+    fn reading_pixel_data_from_a_ppm_file() {
+        let ppm = "P3
+4 3
+255
+255 127 0  0 127 255  127 255 0  255 255 255
+0 0 0  255 0 0  0 255 0  0 0 255
+255 255 0  0 255 255  255 0 255  127 127 127
+";
+        let canvas = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+
+        let expected = [
+            (0, 0, color(1, 0.49804, 0)),
+            (1, 0, color(0, 0.49804, 1)),
+            (2, 0, color(0.49804, 1, 0)),
+            (3, 0, color(1, 1, 1)),
+            (0, 1, color(0, 0, 0)),
+            (1, 1, color(1, 0, 0)),
+            (2, 1, color(0, 1, 0)),
+            (3, 1, color(0, 0, 1)),
+            (0, 2, color(1, 1, 0)),
+            (1, 2, color(0, 1, 1)),
+            (2, 2, color(1, 0, 1)),
+            (3, 2, color(0.49804, 0.49804, 0.49804)),
+        ];
+
+        for (x, y, c) in expected.iter() {
+            assert!(canvas.get_pixel(*x, *y) == c);
+        }
+    }
+
+    #[test]
+    fn ppm_parsing_ignores_comment_lines() {
+        let ppm = "P3
+# this is a comment
+2 1
+# this, too
+255
+# another comment
+255 255 255
+# oh, no, comments in the pixel data!
+255 0 255
+";
+        let canvas = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+
+        assert!(canvas.get_pixel(0, 0) == &color(1, 1, 1));
+        assert!(canvas.get_pixel(1, 0) == &color(1, 0, 1));
+    }
+
+    #[test]
+    fn ppm_parsing_allows_an_rgb_triple_to_span_lines() {
+        let ppm = "P3\n1 1\n255\n51\n153\n\n204\n";
+        let canvas = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+
+        assert!(canvas.get_pixel(0, 0) == &color(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn ppm_parsing_respects_the_scale_setting() {
+        let ppm = "P3\n2 2\n100\n100 100 100  50 50 50\n75 50 25  0 0 0\n";
+        let canvas = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+
+        assert!(canvas.get_pixel(0, 1) == &color(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn ppm_parsing_reports_missing_pixel_data() {
+        let ppm = "P3\n2 1\n255\n255 255 255\n";
+
+        assert!(Canvas::from_ppm(ppm.as_bytes()) == Err(ImageError::UnexpectedEof));
+    }
+
+    #[test]
+    fn ppm_parsing_reports_a_bad_width() {
+        let ppm = "P3\nten 1\n255\n";
+
+        match Canvas::from_ppm(ppm.as_bytes()) {
+            Err(ImageError::MalformedHeader(_)) => {}
+            _ => panic!("expected a malformed header"),
+        }
+    }
+
+    #[test]
+    fn ppm_parsing_rejects_empty_and_oversized_images() {
+        let empty = "P3\n0 3\n255\n";
+        let huge = "P6\n100000 100000\n255\n\0\0\0";
+        let overflowing = format!("P3\n{} {}\n255\n", usize::MAX, usize::MAX);
+
+        assert!(
+            Canvas::from_ppm(empty.as_bytes())
+                == Err(ImageError::MalformedHeader("invalid size 0x3".into()))
+        );
+        assert!(Canvas::from_ppm(huge.as_bytes()) == Err(ImageError::UnexpectedEof));
+        assert!(Canvas::from_ppm(overflowing.as_bytes()) == Err(ImageError::UnexpectedEof));
+    }
+
+    #[test]
+    fn ppm_parsing_rejects_samples_above_the_maximum() {
+        let ascii = "P3\n1 1\n15\n15 16 0\n";
+        let binary = b"P6\n1 1\n100\n\x64\x65\x00";
+
+        assert!(
+            Canvas::from_ppm(ascii.as_bytes())
+                == Err(ImageError::InvalidData(
+                    "sample 16 is above the maximum value 15".into()
+                ))
+        );
+        assert!(
+            Canvas::from_ppm(binary)
+                == Err(ImageError::InvalidData(
+                    "sample 101 is above the maximum value 100".into()
+                ))
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::consts::EPSILON;
 use crate::object::Object;
 use crate::ray::Ray;
-use crate::tuple::Tuple;
+use crate::tuple::{vector, Tuple};
 #[derive(Clone)]
 pub struct Intersection<'a> {
     pub t: f64,
@@ -15,33 +15,59 @@ impl<'a> PartialEq for Intersection<'a> {
 }
 
 impl<'a> Intersection<'a> {
-    pub fn prepare(&self, r: Ray, xs: &[Intersection]) -> ComputedIntersection {
+    pub fn prepare(&self, r: Ray, xs: &[Intersection<'a>]) -> ComputedIntersection<'a> {
         let point = r.position(self.t);
-
-        
-        let mut normal_v = self.object.normal_at(point);
+        // moving objects are shaded in their rest pose, and their normals
+        // carried back to where the object is at the ray's time
+        let to_rest = self.object.rest_pose_at(r.time);
+        let rest_point = to_rest * point;
+        let from_rest = |n: Tuple| match self.object.motion() {
+            Some(_) => {
+                let v = to_rest.transpose() * n;
+                vector(v.x, v.y, v.z).normalize()
+            }
+            None => n,
+        };
+
+        let surface_v = self.object.normal_at(rest_point);
+        // shading uses the bumped normal, but offsets stay on the true surface
+        let normal_v = self
+            .object
+            .material()
+            .bump
+            .perturb(self.object, rest_point, surface_v);
+        let mut surface_v = from_rest(surface_v);
+        let mut normal_v = from_rest(normal_v);
         let eye_v = -r.direction;
-        
+
         let mut is_inside = false;
-        if normal_v.dot(eye_v) < 0.0 {
+        if surface_v.dot(eye_v) < 0.0 {
             is_inside = true;
+            surface_v = -surface_v;
             normal_v = -normal_v;
         }
 
         let reflect_v = r.direction.reflect(normal_v);
 
-        let over_point = point + normal_v * EPSILON;
-        let under_point = point - normal_v * EPSILON;
+        let over_point = point + surface_v * EPSILON;
+        let under_point = point - surface_v * EPSILON;
 
         let mut containers: Vec<&dyn Object> = vec![];
         let mut n1 = 1.0;
         let mut n2 = 1.0;
+        let mut medium = None;
+        let mut reflected_medium = None;
         for x in xs {
             if x == self {
+                reflected_medium = containers.last().copied();
                 if containers.is_empty() {
                     n1 = 1.0;
                 } else {
-                    n1 = containers.last().unwrap().material().refractive_index;
+                    n1 = containers
+                        .last()
+                        .unwrap()
+                        .material()
+                        .refractive_index_at(r.wavelength);
                     //TODO: remove unwrap
                 }
             }
@@ -53,10 +79,15 @@ impl<'a> Intersection<'a> {
             }
 
             if x == self {
+                medium = containers.last().copied();
                 if containers.is_empty() {
                     n2 = 1.0;
                 } else {
-                    n2 = containers.last().unwrap().material().refractive_index;
+                    n2 = containers
+                        .last()
+                        .unwrap()
+                        .material()
+                        .refractive_index_at(r.wavelength);
                     //TODO: remove unwrap
                 }
             }
@@ -71,6 +102,11 @@ impl<'a> Intersection<'a> {
             is_inside,
             n1,
             n2,
+            medium,
+            reflected_medium,
+            wavelength: r.wavelength,
+            time: r.time,
+            rest_point,
             reflect_v,
             over_point,
             under_point,
@@ -113,6 +149,14 @@ pub struct ComputedIntersection<'a> {
     pub under_point: Tuple,
     pub n1: f64,
     pub n2: f64,
+    // the object a refracted ray travels through after leaving this intersection
+    pub medium: Option<&'a dyn Object>,
+    // the object a reflected ray travels back through
+    pub reflected_medium: Option<&'a dyn Object>,
+    pub wavelength: Option<f64>,
+    pub time: f64,
+    // the hit point on the object in its rest pose, where patterns are looked up
+    pub rest_point: Tuple,
 }
 
 impl<'a> ComputedIntersection<'a> {
@@ -138,11 +182,17 @@ impl<'a> ComputedIntersection<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bump::Bump;
+    use crate::mapping::Mapping;
+    use crate::motion::Motion;
     use crate::ray::ray;
     use crate::shapes::{glass_sphere, Plane, Sphere};
-    use crate::transformations::{scale, translate};
+    use crate::spectrum::Dispersion;
+    use crate::texture::{Filter, Texture, Wrap};
+    use crate::transformations::{identity, rotate_y, scale, translate};
     use crate::tuple::{point, vector};
     use std::collections::HashMap;
+    use std::f64::consts::PI;
     #[test]
     fn an_intersection_encapsulates_t_and_object() {
         let s = Sphere::default();
@@ -368,4 +418,97 @@ mod tests {
         let reflectance = comps.schlick();
         assert!(f64::abs(reflectance - 0.48873) < EPSILON);
     }
+
+    #[test]
+    fn medium_is_the_object_the_refracted_ray_travels_through() {
+        let mut a = glass_sphere();
+        a.transform(scale(2, 2, 2));
+        let b = glass_sphere();
+
+        let r = ray(point(0, 0, -4), vector(0, 0, 1));
+        let xs = vec![
+            intersection(2.0, &a),
+            intersection(3.0, &b),
+            intersection(5.0, &b),
+            intersection(6.0, &a),
+        ];
+
+        let expected: [Option<&dyn Object>; 4] = [Some(&a), Some(&b), Some(&a), None];
+        for (x, medium) in xs.iter().zip(expected.iter()) {
+            let comps = x.prepare(r, &xs);
+            assert!(comps.medium == *medium);
+        }
+    }
+
+    #[test]
+    fn reflected_medium_is_the_object_the_ray_arrived_through() {
+        let mut a = glass_sphere();
+        a.transform(scale(2, 2, 2));
+        let b = glass_sphere();
+
+        let r = ray(point(0, 0, -4), vector(0, 0, 1));
+        let xs = vec![
+            intersection(2.0, &a),
+            intersection(3.0, &b),
+            intersection(5.0, &b),
+            intersection(6.0, &a),
+        ];
+
+        let expected: [Option<&dyn Object>; 4] = [None, Some(&a), Some(&b), Some(&a)];
+        for (x, medium) in xs.iter().zip(expected.iter()) {
+            let comps = x.prepare(r, &xs);
+            assert!(comps.reflected_medium == *medium);
+        }
+    }
+
+    #[test]
+    fn n1_and_n2_follow_the_wavelength_of_the_ray() {
+        let mut shape = glass_sphere();
+        shape.material.dispersion = Dispersion::Cauchy(1.5, 0.01);
+        let xs = vec![intersection(4, &shape), intersection(6, &shape)];
+
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let comps = xs[0].prepare(r, &xs);
+        assert!(comps.n2 == 1.5);
+        assert!(comps.wavelength == None);
+
+        let blue = xs[0].prepare(r.with_wavelength(400.0), &xs);
+        let red = xs[0].prepare(r.with_wavelength(700.0), &xs);
+        assert!(f64::abs(blue.n2 - 1.5625) < EPSILON);
+        assert!(blue.n2 > red.n2);
+        assert!(blue.wavelength == Some(400.0));
+    }
+
+    #[test]
+    fn precomputing_uses_the_bumped_normal_for_shading() {
+        let mut image = crate::canvas::Canvas::new(1, 1);
+        image.write_pixel(0, 0, crate::color::color(1, 0.5, 0.5));
+        let texture = Texture::new(image, Filter::Nearest, Wrap::Repeat);
+
+        let mut shape = Plane::default();
+        shape.material.bump = Bump::NormalMap(texture, Mapping::Planar, 1.0);
+        let r = ray(point(-1, 1, 0), vector(1, -1, 0).normalize());
+        let i = intersection(f64::sqrt(2.0), &shape);
+
+        let comps = i.prepare(r, &[i.clone()]);
+        assert!(comps.normal_v == vector(1, 0, 0));
+        assert!(comps.over_point.y > 0.0);
+        assert!(comps.over_point.x == 0.0);
+    }
+
+    #[test]
+    fn normals_of_a_moving_object_follow_it() {
+        let mut s = Sphere::default();
+        s.set_motion(Motion::new(identity(), rotate_y(PI / 2.0).translate(2.0, 0.0, 0.0)).unwrap());
+        let r = ray(point(2, 0, -5), vector(0, 0, 1)).with_time(1.0);
+        let i = intersection(4, &s);
+
+        let comps = i.prepare(r, &[i.clone()]);
+
+        assert!(comps.point == point(2, 0, -1));
+        assert!(comps.normal_v == vector(0, 0, -1));
+        assert!(comps.time == 1.0);
+        // the front of the sphere at time 1 was its right side to begin with
+        assert!(comps.rest_point == point(1, 0, 0));
+    }
 }
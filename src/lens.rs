@@ -0,0 +1,102 @@
+use rand::Rng;
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ApertureShape {
+    Circle,
+    // the number of blades and the rotation of the first corner, in radians
+    Polygon(usize, f64),
+}
+
+// a thin lens in front of the camera. rays start somewhere on the aperture
+// and meet again at the focal distance, so only that plane is sharp.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lens {
+    pub radius: f64,
+    pub focal_distance: f64,
+    pub shape: ApertureShape,
+}
+
+impl Lens {
+    pub fn new(radius: f64, focal_distance: f64, shape: ApertureShape) -> Lens {
+        Lens {
+            radius,
+            focal_distance,
+            shape,
+        }
+    }
+
+    // a uniformly distributed point on the aperture
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> (f64, f64) {
+        match self.shape {
+            ApertureShape::Polygon(blades, rotation) if blades >= 3 => {
+                let corner = |i: usize| {
+                    let angle = rotation + 2.0 * PI * i as f64 / blades as f64;
+                    (self.radius * angle.cos(), self.radius * angle.sin())
+                };
+                let i = rng.gen_range(0, blades);
+                let (a, b) = (corner(i), corner(i + 1));
+
+                // a point in the triangle between the centre and one edge
+                let (mut s, mut t): (f64, f64) = (rng.gen(), rng.gen());
+                if s + t > 1.0 {
+                    s = 1.0 - s;
+                    t = 1.0 - t;
+                }
+                (s * a.0 + t * b.0, s * a.1 + t * b.1)
+            }
+            _ => {
+                let r = self.radius * rng.gen::<f64>().sqrt();
+                let theta = 2.0 * PI * rng.gen::<f64>();
+                (r * theta.cos(), r * theta.sin())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn circular_aperture_samples_lie_on_the_disk() {
+        let lens = Lens::new(0.5, 10.0, ApertureShape::Circle);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..1000 {
+            let (x, y) = lens.sample(&mut rng);
+            assert!(x * x + y * y <= 0.25);
+        }
+    }
+
+    #[test]
+    fn polygonal_aperture_samples_lie_inside_the_blades() {
+        let lens = Lens::new(1.0, 10.0, ApertureShape::Polygon(6, 0.0));
+        let mut rng = StdRng::seed_from_u64(2);
+        // a hexagon with a corner on the x axis is bounded by its apothem
+        let apothem = (PI / 6.0).cos();
+
+        for _ in 0..1000 {
+            let (x, y) = lens.sample(&mut rng);
+            for i in 0..6 {
+                let angle = PI / 6.0 + i as f64 * PI / 3.0;
+                assert!(x * angle.cos() + y * angle.sin() <= apothem + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn aperture_samples_cover_the_whole_shape() {
+        let lens = Lens::new(1.0, 10.0, ApertureShape::Polygon(5, 0.3));
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let samples: Vec<(f64, f64)> = (0..1000).map(|_| lens.sample(&mut rng)).collect();
+        let mean_x = samples.iter().map(|s| s.0).sum::<f64>() / 1000.0;
+        let mean_y = samples.iter().map(|s| s.1).sum::<f64>() / 1000.0;
+
+        assert!(mean_x.abs() < 0.05 && mean_y.abs() < 0.05);
+        assert!(samples.iter().any(|(x, y)| x * x + y * y > 0.5));
+    }
+}
@@ -1,29 +1,59 @@
 pub mod consts;
+pub mod scenes;
 
+mod animation;
+mod bump;
 mod camera;
 mod canvas;
 mod color;
+mod compare;
+mod denoise;
+mod image;
 mod intersection;
+mod lens;
 mod light;
+mod mapping;
 mod material;
 mod matrix;
+mod motion;
+mod noise;
 mod object;
 mod pattern;
+mod post;
 mod ray;
+mod spectrum;
+mod stereo;
+mod texture;
+mod tonemap;
 mod transformations;
 mod tuple;
 mod world;
 mod shapes;
 
+pub use animation::*;
+pub use bump::*;
 pub use camera::*;
+pub use canvas::*;
 pub use color::*;
+pub use compare::*;
+pub use denoise::*;
+pub use image::*;
+pub use lens::*;
 pub use light::*;
+pub use mapping::*;
 pub use material::*;
 pub use matrix::*;
+pub use motion::*;
+pub use noise::*;
 pub use object::*;
 pub use pattern::*;
+pub use post::*;
 pub use shapes::*;
 pub use ray::*;
+pub use spectrum::*;
+pub use stereo::*;
+pub use texture::*;
+pub use tonemap::*;
 pub use transformations::*;
 pub use tuple::*;
 pub use world::*;
@@ -0,0 +1,285 @@
+use crate::color::Color;
+use crate::texture::Texture;
+use crate::tuple::Tuple;
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+// projections from a point in pattern space onto 2D (u, v) texture coordinates,
+// both in the range [0, 1)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+    Cubic,
+}
+
+impl Mapping {
+    pub fn map(&self, p: Tuple) -> (f64, f64) {
+        match self {
+            Mapping::Spherical => spherical_map(p),
+            Mapping::Planar => planar_map(p),
+            Mapping::Cylindrical => cylindrical_map(p),
+            Mapping::Cubic => {
+                let (_, u, v) = cube_map(p);
+                (u, v)
+            }
+        }
+    }
+}
+
+pub fn spherical_map(p: Tuple) -> (f64, f64) {
+    let theta = p.x.atan2(p.z);
+    let radius = (p.x * p.x + p.y * p.y + p.z * p.z).sqrt();
+    let phi = (p.y / radius).acos();
+
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+pub fn planar_map(p: Tuple) -> (f64, f64) {
+    (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0))
+}
+
+pub fn cylindrical_map(p: Tuple) -> (f64, f64) {
+    let theta = p.x.atan2(p.z);
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    (u, p.y.rem_euclid(1.0))
+}
+
+pub fn face_from_point(p: Tuple) -> CubeFace {
+    let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+
+    if coord == p.x {
+        CubeFace::Right
+    } else if coord == -p.x {
+        CubeFace::Left
+    } else if coord == p.y {
+        CubeFace::Up
+    } else if coord == -p.y {
+        CubeFace::Down
+    } else if coord == p.z {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    }
+}
+
+// each face of the cube is unfolded into its own unit square
+pub fn cube_map(p: Tuple) -> (CubeFace, f64, f64) {
+    let face = face_from_point(p);
+    let wrap = |n: f64| n.rem_euclid(2.0) / 2.0;
+
+    let (u, v) = match face {
+        CubeFace::Front => (wrap(p.x + 1.0), wrap(p.y + 1.0)),
+        CubeFace::Back => (wrap(1.0 - p.x), wrap(p.y + 1.0)),
+        CubeFace::Left => (wrap(p.z + 1.0), wrap(p.y + 1.0)),
+        CubeFace::Right => (wrap(1.0 - p.z), wrap(p.y + 1.0)),
+        CubeFace::Up => (wrap(p.x + 1.0), wrap(1.0 - p.z)),
+        CubeFace::Down => (wrap(p.x + 1.0), wrap(p.z + 1.0)),
+    };
+    (face, u, v)
+}
+
+// 2D patterns, evaluated at the (u, v) coordinates produced by a `Mapping`
+#[derive(Clone, Debug, PartialEq)]
+pub enum UvTemplate {
+    Solid(Color),
+    Checkers(f64, f64, Color, Color),
+    // main colour plus one in each corner: upper left, upper right, bottom left, bottom right
+    AlignCheck(Color, Color, Color, Color, Color),
+    Image(Texture),
+}
+
+impl UvTemplate {
+    pub fn color_at(&self, u: f64, v: f64) -> Color {
+        match self {
+            UvTemplate::Solid(c) => *c,
+            UvTemplate::Checkers(width, height, a, b) => {
+                let u2 = (u * width).floor();
+                let v2 = (v * height).floor();
+                match (u2 + v2) % 2.0 == 0.0 {
+                    true => *a,
+                    false => *b,
+                }
+            }
+            UvTemplate::AlignCheck(main, ul, ur, bl, br) => {
+                if v > 0.8 {
+                    if u < 0.2 {
+                        return *ul;
+                    } else if u > 0.8 {
+                        return *ur;
+                    }
+                } else if v < 0.2 {
+                    if u < 0.2 {
+                        return *bl;
+                    } else if u > 0.8 {
+                        return *br;
+                    }
+                }
+                *main
+            }
+            UvTemplate::Image(texture) => texture.color_at(u, v),
+        }
+    }
+}
+
+pub fn uv_checkers<A: Into<f64>, B: Into<f64>>(
+    width: A,
+    height: B,
+    color_a: Color,
+    color_b: Color,
+) -> UvTemplate {
+    UvTemplate::Checkers(width.into(), height.into(), color_a, color_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{color, BLACK, WHITE};
+    use crate::tuple::point;
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let checkers = uv_checkers(2, 2, BLACK, WHITE);
+
+        assert!(checkers.color_at(0.0, 0.0) == BLACK);
+        assert!(checkers.color_at(0.5, 0.0) == WHITE);
+        assert!(checkers.color_at(0.0, 0.5) == WHITE);
+        assert!(checkers.color_at(0.5, 0.5) == BLACK);
+        assert!(checkers.color_at(1.0, 1.0) == BLACK);
+    }
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let expected = [
+            (point(0, 0, -1), 0.0, 0.5),
+            (point(1, 0, 0), 0.25, 0.5),
+            (point(0, 0, 1), 0.5, 0.5),
+            (point(-1, 0, 0), 0.75, 0.5),
+            (point(0, 1, 0), 0.5, 1.0),
+            (point(0, -1, 0), 0.5, 0.0),
+            (
+                point(f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0, 0),
+                0.25,
+                0.75,
+            ),
+        ];
+
+        for (p, u, v) in expected.iter() {
+            let (mu, mv) = spherical_map(*p);
+            assert!((mu - u).abs() < 0.0001);
+            assert!((mv - v).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let expected = [
+            (point(0.25, 0, 0.5), 0.25, 0.5),
+            (point(0.25, 0, -0.25), 0.25, 0.75),
+            (point(0.25, 0.5, -0.25), 0.25, 0.75),
+            (point(1.25, 0, 0.5), 0.25, 0.5),
+            (point(0.25, 0, -1.75), 0.25, 0.25),
+            (point(1, 0, -1), 0.0, 0.0),
+            (point(0, 0, 0), 0.0, 0.0),
+        ];
+
+        for (p, u, v) in expected.iter() {
+            let (mu, mv) = planar_map(*p);
+            assert!((mu - u).abs() < 0.0001);
+            assert!((mv - v).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let expected = [
+            (point(0, 0, -1), 0.0, 0.0),
+            (point(0, 0.5, -1), 0.0, 0.5),
+            (point(0, 1, -1), 0.0, 0.0),
+            (point(0.70711, 0.5, -0.70711), 0.125, 0.5),
+            (point(1, 0.5, 0), 0.25, 0.5),
+            (point(0.70711, 0.5, 0.70711), 0.375, 0.5),
+            (point(0, -0.25, 1), 0.5, 0.75),
+            (point(-0.70711, 0.5, 0.70711), 0.625, 0.5),
+            (point(-1, 1.25, 0), 0.75, 0.25),
+            (point(-0.70711, 0.5, -0.70711), 0.875, 0.5),
+        ];
+
+        for (p, u, v) in expected.iter() {
+            let (mu, mv) = cylindrical_map(*p);
+            assert!((mu - u).abs() < 0.0001);
+            assert!((mv - v).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn layout_of_the_align_check_pattern() {
+        let main = color(1, 1, 1);
+        let ul = color(1, 0, 0);
+        let ur = color(1, 1, 0);
+        let bl = color(0, 1, 0);
+        let br = color(0, 1, 1);
+        let pattern = UvTemplate::AlignCheck(main, ul, ur, bl, br);
+
+        assert!(pattern.color_at(0.5, 0.5) == main);
+        assert!(pattern.color_at(0.1, 0.9) == ul);
+        assert!(pattern.color_at(0.9, 0.9) == ur);
+        assert!(pattern.color_at(0.1, 0.1) == bl);
+        assert!(pattern.color_at(0.9, 0.1) == br);
+    }
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let expected = [
+            (point(-1, 0.5, -0.25), CubeFace::Left),
+            (point(1.1, -0.75, 0.8), CubeFace::Right),
+            (point(0.1, 0.6, 0.9), CubeFace::Front),
+            (point(-0.7, 0, -2), CubeFace::Back),
+            (point(0.5, 1, 0.9), CubeFace::Up),
+            (point(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+
+        for (p, face) in expected.iter() {
+            assert!(face_from_point(*p) == *face);
+        }
+    }
+
+    #[test]
+    fn uv_mapping_the_faces_of_a_cube() {
+        let expected = [
+            (point(-0.5, 0.5, 1), CubeFace::Front, 0.25, 0.75),
+            (point(0.5, -0.5, 1), CubeFace::Front, 0.75, 0.25),
+            (point(0.5, 0.5, -1), CubeFace::Back, 0.25, 0.75),
+            (point(-0.5, -0.5, -1), CubeFace::Back, 0.75, 0.25),
+            (point(-1, 0.5, -0.5), CubeFace::Left, 0.25, 0.75),
+            (point(-1, -0.5, 0.5), CubeFace::Left, 0.75, 0.25),
+            (point(1, 0.5, 0.5), CubeFace::Right, 0.25, 0.75),
+            (point(1, -0.5, -0.5), CubeFace::Right, 0.75, 0.25),
+            (point(-0.5, 1, -0.5), CubeFace::Up, 0.25, 0.75),
+            (point(0.5, 1, 0.5), CubeFace::Up, 0.75, 0.25),
+            (point(-0.5, -1, 0.5), CubeFace::Down, 0.25, 0.75),
+            (point(0.5, -1, -0.5), CubeFace::Down, 0.75, 0.25),
+        ];
+
+        for (p, face, u, v) in expected.iter() {
+            let (f, mu, mv) = cube_map(*p);
+            assert!(f == *face);
+            assert!((mu - u).abs() < 0.0001);
+            assert!((mv - v).abs() < 0.0001);
+        }
+    }
+}
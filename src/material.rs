@@ -1,11 +1,14 @@
-use crate::color::{color, Color};
+use crate::bump::Bump;
+use crate::color::{color, Color, WHITE};
 use crate::consts::EPSILON;
 use crate::light::PointLight;
 use crate::object::Object;
 use crate::pattern::{solid_pattern, Pattern};
+use crate::spectrum::Dispersion;
 use crate::tuple::Tuple;
 
-#[derive(Copy, Clone, Debug)]
+// not `Copy`, as patterns, masks and bump maps may hold textures
+#[derive(Clone, Debug)]
 pub struct Material {
     pub ambient: f64,
     pub diffuse: f64,
@@ -15,6 +18,13 @@ pub struct Material {
     pub transparency: f64,
     pub refractive_index: f64,
     pub pattern: Pattern,
+    pub absorption: Color,
+    pub density: f64,
+    pub dispersion: Dispersion,
+    pub bump: Bump,
+    // surfaces are skipped entirely where the mask is darker than the threshold
+    pub mask: Option<Pattern>,
+    pub mask_threshold: f64,
 }
 
 impl Material {
@@ -37,6 +47,12 @@ impl Material {
             transparency,
             refractive_index,
             pattern: pattern,
+            absorption: WHITE,
+            density: 0.0,
+            dispersion: Dispersion::None,
+            bump: Bump::None,
+            mask: None,
+            mask_threshold: 0.5,
         }
     }
 
@@ -53,6 +69,44 @@ impl Material {
         )
     }
 
+    pub fn refractive_index_at(&self, wavelength: Option<f64>) -> f64 {
+        match wavelength {
+            Some(w) => self.dispersion.refractive_index(w, self.refractive_index),
+            None => self.refractive_index,
+        }
+    }
+
+    // Beer-Lambert attenuation for light travelling `distance` through the material.
+    // `absorption` is the colour transmitted through one unit at a density of 1.0
+    pub fn transmittance(&self, distance: f64) -> Color {
+        if self.density == 0.0 {
+            return WHITE;
+        }
+
+        let k = self.density * distance;
+        color(
+            self.absorption.0.powf(k),
+            self.absorption.1.powf(k),
+            self.absorption.2.powf(k),
+        )
+    }
+
+    pub fn is_cut_out(&self, object: &dyn Object, point: Tuple) -> bool {
+        match &self.mask {
+            None => false,
+            Some(mask) => {
+                let c = mask.color_at_shape(object, object.inverse() * point);
+                (c.0 + c.1 + c.2) / 3.0 < self.mask_threshold
+            }
+        }
+    }
+
+    // the unlit colour of the surface at a world space point
+    pub fn albedo_at(&self, object: &dyn Object, point: Tuple) -> Color {
+        self.pattern
+            .color_at_shape(object, object.inverse() * point)
+    }
+
     // TODO: don't calculate specular and diffuse if in shadow
     pub fn lighting(
         &self,
@@ -63,10 +117,27 @@ impl Material {
         normal_v: Tuple,
         in_shadow: bool,
     ) -> Color {
-        let object_point = object.inverse() * point;
-        let start_color = self.pattern.color_at_object(object_point);
+        self.lighting_with_albedo(
+            self.albedo_at(object, point),
+            light,
+            point,
+            eye_v,
+            normal_v,
+            in_shadow,
+        )
+    }
 
-        let effective_color = start_color * light.intensity;
+    // lighting for a surface whose unlit colour is already known
+    pub fn lighting_with_albedo(
+        &self,
+        albedo: Color,
+        light: &PointLight,
+        point: Tuple,
+        eye_v: Tuple,
+        normal_v: Tuple,
+        in_shadow: bool,
+    ) -> Color {
+        let effective_color = albedo * light.intensity;
         let ambient = effective_color * self.ambient;
 
         if in_shadow {
@@ -272,4 +343,65 @@ mod tests {
         assert!(m.refractive_index == 1.0);
         assert!(m.transparency == 0.0);
     }
+
+    #[test]
+    fn refractive_index_ignores_dispersion_without_a_wavelength() {
+        let mut m = Material::default();
+        m.refractive_index = 1.5;
+        m.dispersion = Dispersion::Cauchy(1.5046, 0.0042);
+
+        assert!(m.refractive_index_at(None) == 1.5);
+        assert!(f64::abs(m.refractive_index_at(Some(500.0)) - 1.5214) < EPSILON);
+    }
+
+    #[test]
+    fn default_material_has_no_mask() {
+        let object = Sphere::default();
+        let m = Material::default();
+
+        assert!(m.mask == None);
+        assert!(!m.is_cut_out(&object, point(0, 0, -1)));
+    }
+
+    #[test]
+    fn mask_below_threshold_cuts_out_the_surface() {
+        let object = Sphere::default();
+        let mut m = Material::default();
+        m.mask = Some(stripe_pattern(color(1, 1, 1), color(0.2, 0.2, 0.2), None));
+
+        assert!(!m.is_cut_out(&object, point(0.5, 0, 0)));
+        assert!(m.is_cut_out(&object, point(1.5, 0, 0)));
+
+        m.mask_threshold = 0.1;
+        assert!(!m.is_cut_out(&object, point(1.5, 0, 0)));
+    }
+
+    #[test]
+    fn default_material_does_not_absorb() {
+        let m = Material::default();
+
+        assert!(m.transmittance(0.0) == WHITE);
+        assert!(m.transmittance(100.0) == WHITE);
+    }
+
+    #[test]
+    fn transmittance_falls_off_with_distance() {
+        let mut m = Material::default();
+        m.absorption = color(0.5, 1, 0);
+        m.density = 1.0;
+
+        assert!(m.transmittance(0.0) == WHITE);
+        assert!(m.transmittance(1.0) == color(0.5, 1, 0));
+        assert!(m.transmittance(2.0) == color(0.25, 1, 0));
+        assert!(m.transmittance(f64::INFINITY) == color(0, 1, 0));
+    }
+
+    #[test]
+    fn density_scales_absorption() {
+        let mut m = Material::default();
+        m.absorption = color(0.5, 0.5, 0.5);
+        m.density = 2.0;
+
+        assert!(m.transmittance(1.0) == color(0.25, 0.25, 0.25));
+    }
 }
@@ -0,0 +1,437 @@
+use crate::matrix::Matrix;
+use crate::tuple::{vector, Tuple};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum MotionError {
+    NoKeyframes,
+    InvalidTime(f64),
+    Degenerate(f64),
+}
+
+impl fmt::Display for MotionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MotionError::NoKeyframes => write!(f, "motion needs at least one keyframe"),
+            MotionError::InvalidTime(time) => write!(f, "keyframe time {} isn't finite", time),
+            MotionError::Degenerate(time) => write!(f, "keyframe at time {} is degenerate", time),
+        }
+    }
+}
+
+impl std::error::Error for MotionError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w, x, y, z }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let length = self.dot(self).sqrt();
+        Quaternion::new(
+            self.w / length,
+            self.x / length,
+            self.y / length,
+            self.z / length,
+        )
+    }
+
+    // the rotation held in the upper left 3x3 of a matrix, which must be
+    // orthonormal
+    pub fn from_rotation(m: &Matrix) -> Quaternion {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Quaternion::new(
+                s / 4.0,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                s / 4.0,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Quaternion::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                s / 4.0,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Quaternion::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.0,
+            )
+        };
+        q.normalize()
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        let Quaternion { w, x, y, z } = *self;
+        Matrix::from([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // spherical interpolation along the shorter arc
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut other = *other;
+        let mut cos = self.dot(&other);
+        if cos < 0.0 {
+            other = Quaternion::new(-other.w, -other.x, -other.y, -other.z);
+            cos = -cos;
+        }
+
+        let (a, b) = match cos > 0.9995 {
+            // nearly parallel, where a straight line is accurate enough
+            true => (1.0 - t, t),
+            false => {
+                let angle = cos.acos();
+                let sin = angle.sin();
+                (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+            }
+        };
+
+        Quaternion::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+        .normalize()
+    }
+}
+
+// an affine transform split into parts that can be interpolated separately.
+// shear isn't represented, so decomposing a sheared matrix loses it, and a
+// matrix that scales an axis to nothing gives NaNs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decomposed {
+    pub translation: Tuple,
+    pub rotation: Quaternion,
+    pub scale: Tuple,
+}
+
+impl Decomposed {
+    pub fn new(m: &Matrix) -> Decomposed {
+        let column = |c: usize| vector(m[0][c], m[1][c], m[2][c]);
+        let (x, y, z) = (column(0), column(1), column(2));
+
+        let mut scale = vector(x.magnitude(), y.magnitude(), z.magnitude());
+        // a mirrored matrix is a rotation with one negative scale
+        if x.cross(&y).dot(z) < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let (x, y, z) = (x / scale.x, y / scale.y, z / scale.z);
+        let rotation = Matrix::from([
+            [x.x, y.x, z.x, 0.0],
+            [x.y, y.y, z.y, 0.0],
+            [x.z, y.z, z.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Decomposed {
+            translation: vector(m[0][3], m[1][3], m[2][3]),
+            rotation: Quaternion::from_rotation(&rotation),
+            scale,
+        }
+    }
+
+    // translation * rotation * scale
+    pub fn to_matrix(&self) -> Matrix {
+        let r = self.rotation.to_matrix();
+        let (s, t) = (self.scale, self.translation);
+        Matrix::from([
+            [r[0][0] * s.x, r[0][1] * s.y, r[0][2] * s.z, t.x],
+            [r[1][0] * s.x, r[1][1] * s.y, r[1][2] * s.z, t.y],
+            [r[2][0] * s.x, r[2][1] * s.y, r[2][2] * s.z, t.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // scale⁻¹ * rotation⁻¹ * translation⁻¹, worked out directly as the
+    // rotation's inverse is its transpose
+    pub fn to_inverse_matrix(&self) -> Matrix {
+        let r = self.rotation.to_matrix();
+        let s = vector(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        let row = |i: usize, s: f64| [r[0][i] * s, r[1][i] * s, r[2][i] * s];
+        let (x, y, z) = (row(0, s.x), row(1, s.y), row(2, s.z));
+        let t = self.translation;
+        Matrix::from([
+            [x[0], x[1], x[2], -(x[0] * t.x + x[1] * t.y + x[2] * t.z)],
+            [y[0], y[1], y[2], -(y[0] * t.x + y[1] * t.y + y[2] * t.z)],
+            [z[0], z[1], z[2], -(z[0] * t.x + z[1] * t.y + z[2] * t.z)],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn interpolate(&self, other: &Decomposed, t: f64) -> Decomposed {
+        Decomposed {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale + (other.scale - self.scale) * t,
+        }
+    }
+}
+
+// an object's transform over the shutter interval, as keyframes at times
+// in increasing order. times before the first or after the last key hold
+// still.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Motion {
+    keys: Vec<(f64, Decomposed)>,
+}
+
+impl Motion {
+    // moves from `start` at time 0 to `end` at time 1
+    pub fn new(start: Matrix, end: Matrix) -> Result<Motion, MotionError> {
+        Ok(Motion {
+            keys: vec![(0.0, decompose(0.0, &start)?), (1.0, decompose(1.0, &end)?)],
+        })
+    }
+
+    pub fn keyframes(keys: Vec<(f64, Matrix)>) -> Result<Motion, MotionError> {
+        if keys.is_empty() {
+            return Err(MotionError::NoKeyframes);
+        }
+        if let Some((time, _)) = keys.iter().find(|(time, _)| !time.is_finite()) {
+            return Err(MotionError::InvalidTime(*time));
+        }
+
+        let mut keys = keys
+            .iter()
+            .map(|(time, m)| Ok((*time, decompose(*time, m)?)))
+            .collect::<Result<Vec<(f64, Decomposed)>, MotionError>>()?;
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Motion { keys })
+    }
+
+    // the same motion with `matrix` applied after every keyframe. any shear
+    // that adds is lost, as in `Decomposed`.
+    pub fn transformed(&self, matrix: &Matrix) -> Result<Motion, MotionError> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|(time, d)| Ok((*time, decompose(*time, &(*matrix * d.to_matrix()))?)))
+            .collect::<Result<Vec<(f64, Decomposed)>, MotionError>>()?;
+        Ok(Motion { keys })
+    }
+
+    pub fn transform_at(&self, time: f64) -> Matrix {
+        self.pose_at(time).to_matrix()
+    }
+
+    pub fn inverse_at(&self, time: f64) -> Matrix {
+        self.pose_at(time).to_inverse_matrix()
+    }
+
+    fn pose_at(&self, time: f64) -> Decomposed {
+        let first = &self.keys[0];
+        let last = &self.keys[self.keys.len() - 1];
+        if time <= first.0 {
+            return first.1;
+        }
+        if time >= last.0 {
+            return last.1;
+        }
+
+        let i = self.keys.iter().position(|(t, _)| *t > time).unwrap();
+        let (t0, a) = &self.keys[i - 1];
+        let (t1, b) = &self.keys[i];
+        a.interpolate(b, (time - t0) / (t1 - t0))
+    }
+}
+
+fn decompose(time: f64, m: &Matrix) -> Result<Decomposed, MotionError> {
+    let d = Decomposed::new(m);
+    let (s, t, q) = (d.scale, d.translation, d.rotation);
+    let finite = [s.x, s.y, s.z, t.x, t.y, t.z, q.w, q.x, q.y, q.z]
+        .iter()
+        .all(|v| v.is_finite());
+    match finite && s.x != 0.0 && s.y != 0.0 && s.z != 0.0 {
+        true => Ok(d),
+        false => Err(MotionError::Degenerate(time)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transformations::*;
+    use crate::tuple::point;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn quaternions_round_trip_rotations() {
+        for m in [
+            rotate_x(0.3),
+            rotate_y(2.0),
+            rotate_z(-1.2),
+            rotate_x(PI).rotate_y(0.5),
+        ]
+        .iter()
+        {
+            assert!(Quaternion::from_rotation(m).to_matrix() == *m);
+        }
+    }
+
+    #[test]
+    fn slerp_halfway_between_rotations() {
+        let a = Quaternion::from_rotation(&identity());
+        let b = Quaternion::from_rotation(&rotate_z(PI / 2.0));
+
+        assert!(a.slerp(&b, 0.5).to_matrix() == rotate_z(PI / 4.0));
+    }
+
+    #[test]
+    fn slerp_takes_the_shorter_arc() {
+        let a = Quaternion::from_rotation(&rotate_y(-3.0));
+        let b = Quaternion::from_rotation(&rotate_y(3.0));
+
+        // halfway round the short way is facing backwards
+        assert!(a.slerp(&b, 0.5).to_matrix() == rotate_y(PI));
+    }
+
+    #[test]
+    fn decomposing_a_transform() {
+        let m = scale(2, 3, 4).rotate_y(PI / 3.0).translate(1.0, -2.0, 5.0);
+        let d = Decomposed::new(&m);
+
+        assert!(d.translation == vector(1, -2, 5));
+        assert!(d.scale == vector(2, 3, 4));
+        assert!(d.rotation.to_matrix() == rotate_y(PI / 3.0));
+        assert!(d.to_matrix() == m);
+    }
+
+    #[test]
+    fn decomposing_a_mirrored_transform() {
+        let m = scale(-1, 2, 1).rotate_z(0.4);
+
+        assert!(Decomposed::new(&m).to_matrix() == m);
+    }
+
+    #[test]
+    fn motion_interpolates_between_start_and_end() {
+        let motion = Motion::new(identity(), translate(4, 0, 0)).unwrap();
+
+        assert!(motion.transform_at(0.0) == identity());
+        assert!(motion.transform_at(0.25) == translate(1, 0, 0));
+        assert!(motion.transform_at(1.0) == translate(4, 0, 0));
+        assert!(motion.transform_at(2.0) == translate(4, 0, 0));
+    }
+
+    #[test]
+    fn motion_rotates_rather_than_blending_matrices() {
+        let motion = Motion::new(identity(), rotate_z(PI)).unwrap();
+        let halfway = motion.transform_at(0.5);
+
+        // averaging the matrices would collapse the point onto the axis
+        assert!(halfway * point(1, 0, 0) == point(0, 1, 0));
+    }
+
+    #[test]
+    fn motion_follows_keyframes_in_time_order() {
+        let motion = Motion::keyframes(vec![
+            (1.0, translate(0, 2, 0)),
+            (0.0, identity()),
+            (0.5, scale(2, 2, 2).translate(0.0, 1.0, 0.0)),
+        ])
+        .unwrap();
+
+        assert!(motion.transform_at(0.25) == scale(1.5, 1.5, 1.5).translate(0.0, 0.5, 0.0));
+        assert!(motion.transform_at(0.75) == scale(1.5, 1.5, 1.5).translate(0.0, 1.5, 0.0));
+    }
+
+    #[test]
+    fn inverting_a_decomposed_transform() {
+        let m = scale(2, 3, -4).rotate_y(PI / 3.0).translate(1.0, -2.0, 5.0);
+        let d = Decomposed::new(&m);
+
+        assert!(d.to_inverse_matrix() == m.inverse().unwrap());
+    }
+
+    #[test]
+    fn motion_inverse_matches_the_inverted_transform() {
+        let motion =
+            Motion::new(scale(1, 2, 1), rotate_z(PI / 2.0).translate(4.0, 0.0, 0.0)).unwrap();
+
+        for time in [-1.0, 0.0, 0.3, 0.7, 2.0].iter() {
+            let m = motion.transform_at(*time);
+            assert!(motion.inverse_at(*time) == m.inverse().unwrap());
+        }
+    }
+
+    #[test]
+    fn motion_needs_keyframes() {
+        assert!(Motion::keyframes(vec![]) == Err(MotionError::NoKeyframes));
+    }
+
+    #[test]
+    fn motion_rejects_times_that_are_not_finite() {
+        let keys = vec![(0.0, identity()), (f64::INFINITY, translate(1, 0, 0))];
+        assert!(Motion::keyframes(keys) == Err(MotionError::InvalidTime(f64::INFINITY)));
+
+        let keys = vec![(f64::NAN, identity())];
+        assert!(Motion::keyframes(keys).is_err());
+    }
+
+    #[test]
+    fn motion_rejects_keyframes_that_flatten_the_object() {
+        assert!(Motion::new(identity(), scale(0, 1, 1)) == Err(MotionError::Degenerate(1.0)));
+
+        let keys = vec![(0.0, identity()), (0.5, Matrix::from([[0.0; 4]; 4]))];
+        assert!(Motion::keyframes(keys) == Err(MotionError::Degenerate(0.5)));
+    }
+
+    #[test]
+    fn transforming_a_motion_applies_after_every_keyframe() {
+        let motion = Motion::new(identity(), translate(4, 0, 0)).unwrap();
+        let moved = motion.transformed(&rotate_z(PI / 2.0)).unwrap();
+
+        assert!(moved.transform_at(0.0) == rotate_z(PI / 2.0));
+        assert!(moved.transform_at(0.5) == rotate_z(PI / 2.0) * translate(2, 0, 0));
+        assert!(moved.transform_at(1.0) == rotate_z(PI / 2.0) * translate(4, 0, 0));
+        assert!(motion.transformed(&scale(1, 0, 1)) == Err(MotionError::Degenerate(0.0)));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::tuple::Tuple;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoiseKind {
+    Perlin,
+    Simplex,
+}
+
+// seeded gradient noise. Values are roughly in the range [-1, 1] and the same
+// seed always produces the same field, so renders are reproducible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Noise {
+    pub kind: NoiseKind,
+    // number of layers summed by `fbm` and `turbulence`
+    pub octaves: usize,
+    perm: Vec<u8>,
+}
+
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+// splitmix64, used only to shuffle the permutation table
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn dot(g: [f64; 3], x: f64, y: f64, z: f64) -> f64 {
+    g[0] * x + g[1] * y + g[2] * z
+}
+
+impl Noise {
+    pub fn new(seed: u64, kind: NoiseKind) -> Noise {
+        let mut table: Vec<u8> = (0..=255).collect();
+        let mut state = seed;
+        for i in (1..256).rev() {
+            let j = (next_random(&mut state) % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        let mut perm = table.clone();
+        perm.extend(table);
+        Noise {
+            kind,
+            octaves: 4,
+            perm,
+        }
+    }
+
+    pub fn perlin(seed: u64) -> Noise {
+        Noise::new(seed, NoiseKind::Perlin)
+    }
+
+    pub fn simplex(seed: u64) -> Noise {
+        Noise::new(seed, NoiseKind::Simplex)
+    }
+
+    pub fn with_octaves(self, octaves: usize) -> Noise {
+        Noise { octaves, ..self }
+    }
+
+    pub fn sample(&self, p: Tuple) -> f64 {
+        match self.kind {
+            NoiseKind::Perlin => self.perlin_at(p.x, p.y, p.z),
+            NoiseKind::Simplex => self.simplex_at(p.x, p.y, p.z),
+        }
+    }
+
+    // fractional Brownian motion: octaves of noise at doubling frequency and
+    // halving amplitude, normalised back into [-1, 1]
+    pub fn fbm(&self, p: Tuple) -> f64 {
+        let (sum, total) = self.sum_octaves(p, |n| n);
+        sum / total
+    }
+
+    // like `fbm` but summing the absolute value of each octave, in [0, 1]
+    pub fn turbulence(&self, p: Tuple) -> f64 {
+        let (sum, total) = self.sum_octaves(p, f64::abs);
+        sum / total
+    }
+
+    fn sum_octaves<F: Fn(f64) -> f64>(&self, p: Tuple, f: F) -> (f64, f64) {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..self.octaves.max(1) {
+            sum += f(self.sample(p * frequency)) * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        (sum, total)
+    }
+
+    fn hash(&self, x: i64, y: i64, z: i64) -> usize {
+        let x = (x & 255) as usize;
+        let y = (y & 255) as usize;
+        let z = (z & 255) as usize;
+        self.perm[self.perm[self.perm[x] as usize + y] as usize + z] as usize
+    }
+
+    fn gradient(&self, x: i64, y: i64, z: i64) -> [f64; 3] {
+        GRADIENTS[self.hash(x, y, z) % 12]
+    }
+
+    fn perlin_at(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (xi, yi, zi) = (x0 as i64, y0 as i64, z0 as i64);
+        let (x, y, z) = (x - x0, y - y0, z - z0);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let corner = |dx: i64, dy: i64, dz: i64| {
+            let g = self.gradient(xi + dx, yi + dy, zi + dz);
+            dot(g, x - dx as f64, y - dy as f64, z - dz as f64)
+        };
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+                lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+            ),
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+                lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+            ),
+        )
+    }
+
+    fn simplex_at(&self, x: f64, y: f64, z: f64) -> f64 {
+        const F3: f64 = 1.0 / 3.0;
+        const G3: f64 = 1.0 / 6.0;
+
+        // skew into the simplex grid and find the containing cell
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+        let z0 = z - (k - t);
+
+        // which of the six tetrahedra in the cell the point lies in
+        let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+            if y0 >= z0 {
+                (1, 0, 0, 1, 1, 0)
+            } else if x0 >= z0 {
+                (1, 0, 0, 1, 0, 1)
+            } else {
+                (0, 0, 1, 1, 0, 1)
+            }
+        } else if y0 < z0 {
+            (0, 0, 1, 0, 1, 1)
+        } else if x0 < z0 {
+            (0, 1, 0, 0, 1, 1)
+        } else {
+            (0, 1, 0, 1, 1, 0)
+        };
+
+        let (i, j, k) = (i as i64, j as i64, k as i64);
+        let corners = [
+            (0, 0, 0, 0.0),
+            (i1, j1, k1, G3),
+            (i2, j2, k2, 2.0 * G3),
+            (1, 1, 1, 3.0 * G3),
+        ];
+
+        let total = corners.iter().fold(0.0, |acc, (di, dj, dk, offset)| {
+            let cx = x0 - *di as f64 + offset;
+            let cy = y0 - *dj as f64 + offset;
+            let cz = z0 - *dk as f64 + offset;
+            let t = 0.6 - cx * cx - cy * cy - cz * cz;
+            if t < 0.0 {
+                acc
+            } else {
+                let g = self.gradient(i + di, j + dj, k + dk);
+                acc + t.powi(4) * dot(g, cx, cy, cz)
+            }
+        });
+
+        32.0 * total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuple::point;
+
+    fn grid() -> Vec<Tuple> {
+        let mut points = vec![];
+        for x in 0..10 {
+            for y in 0..10 {
+                for z in 0..10 {
+                    points.push(point(x as f64 * 0.37, y as f64 * 0.53, z as f64 * 0.71));
+                }
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn noise_is_deterministic_for_a_seed() {
+        let a = Noise::perlin(42);
+        let b = Noise::perlin(42);
+        let p = point(1.3, 2.7, -0.4);
+
+        assert!(a == b);
+        assert!(a.sample(p) == b.sample(p));
+        assert!(Noise::simplex(7).sample(p) == Noise::simplex(7).sample(p));
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        let a = Noise::perlin(1);
+        let b = Noise::perlin(2);
+
+        assert!(grid().iter().any(|p| a.sample(*p) != b.sample(*p)));
+    }
+
+    #[test]
+    fn perlin_noise_is_zero_on_lattice_points() {
+        let noise = Noise::perlin(3);
+
+        assert!(noise.sample(point(0, 0, 0)) == 0.0);
+        assert!(noise.sample(point(4, -2, 7)) == 0.0);
+    }
+
+    #[test]
+    fn noise_stays_within_range() {
+        for noise in [Noise::perlin(5), Noise::simplex(5)].iter() {
+            for p in grid() {
+                let n = noise.sample(p);
+                assert!((-1.0..=1.0).contains(&n));
+            }
+        }
+    }
+
+    #[test]
+    fn noise_varies_smoothly() {
+        let noise = Noise::simplex(11);
+        let p = point(0.3, 0.6, 0.9);
+        let q = point(0.301, 0.6, 0.9);
+
+        assert!(noise.sample(p) != noise.sample(q));
+        assert!((noise.sample(p) - noise.sample(q)).abs() < 0.05);
+    }
+
+    #[test]
+    fn fbm_and_turbulence_are_normalised() {
+        let noise = Noise::perlin(9).with_octaves(6);
+
+        for p in grid() {
+            assert!((-1.0..=1.0).contains(&noise.fbm(p)));
+            assert!((0.0..=1.0).contains(&noise.turbulence(p)));
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use crate::consts::EPSILON;
 use crate::intersection::Intersection;
+use crate::mapping::Mapping;
 use crate::material::Material;
 use crate::matrix::Matrix;
+use crate::motion::Motion;
 use crate::ray::Ray;
-use crate::tuple::Tuple;
+use crate::tuple::{vector, Tuple};
 use uuid::Uuid;
 
 pub trait Object {
@@ -15,6 +18,49 @@ pub trait Object {
     fn material(&self) -> &Material;
     fn material_mut(&mut self) -> &mut Material;
     fn id(&self) -> Uuid;
+    // animates the object over the shutter interval. its transform becomes
+    // the pose at time 0, which is what normals and patterns are worked out in.
+    // the motion replaces any earlier transform, and later transforms apply
+    // on top of every keyframe. objects that can't move ignore it.
+    fn set_motion(&mut self, _motion: Motion) {}
+
+    fn motion(&self) -> Option<&Motion> {
+        None
+    }
+
+    // unit vector along the surface in the direction u increases in `uv_mapping`.
+    // the default only promises some tangent, so shapes should override it.
+    fn tangent_at(&self, p: Tuple) -> Tuple {
+        tangent_from_normal(self.normal_at(p))
+    }
+
+    // how a 2D texture wraps onto the shape when its pattern doesn't say
+    fn uv_mapping(&self) -> Mapping {
+        Mapping::Spherical
+    }
+
+    fn transformation_at(&self, time: f64) -> Matrix {
+        match self.motion() {
+            Some(motion) => motion.transform_at(time),
+            None => self.transformation(),
+        }
+    }
+
+    fn inverse_at(&self, time: f64) -> Matrix {
+        match self.motion() {
+            Some(motion) => motion.inverse_at(time),
+            None => self.inverse(),
+        }
+    }
+
+    // maps a point on the object as it is at `time` to the same point on it
+    // in its rest pose
+    fn rest_pose_at(&self, time: f64) -> Matrix {
+        match self.motion() {
+            Some(_) => self.transformation() * self.inverse_at(time),
+            None => Matrix::identity(),
+        }
+    }
 }
 
 impl PartialEq for &dyn Object {
@@ -22,3 +68,35 @@ impl PartialEq for &dyn Object {
         self.id() == other.id()
     }
 }
+
+// a tangent running around the y axis, or along x at the poles
+fn tangent_from_normal(normal: Tuple) -> Tuple {
+    let axis = match normal.x.abs() < EPSILON && normal.z.abs() < EPSILON {
+        true => vector(0, 0, 1),
+        false => vector(0, 1, 0),
+    };
+    normal.cross(&axis).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_tangent_is_perpendicular_to_the_normal() {
+        let normals = [
+            vector(0, 0, -1),
+            vector(1, 0, 0),
+            vector(0, 1, 0),
+            vector(0, -1, 0),
+            vector(0.6, 0.8, 0),
+        ];
+
+        for n in normals.iter() {
+            let t = tangent_from_normal(*n);
+            assert!(t.dot(*n).abs() < EPSILON);
+            assert!((t.magnitude() - 1.0).abs() < EPSILON);
+        }
+        assert!(tangent_from_normal(vector(0, 0, -1)) == vector(1, 0, 0));
+    }
+}
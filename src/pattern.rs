@@ -1,47 +1,156 @@
 use crate::color::Color;
+use crate::mapping::{cube_map, CubeFace, Mapping, UvTemplate};
 use crate::matrix::Matrix;
-use crate::tuple::Tuple;
-#[derive(Copy, Clone, Debug, PartialEq)]
+use crate::noise::Noise;
+use crate::object::Object;
+use crate::tuple::{vector, Tuple};
+use std::f64::consts::PI;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+// implemented by patterns defined outside the library. `point` is in pattern
+// space, so the owning `Pattern`'s transform has already been applied.
+pub trait PatternSource: Debug {
+    fn color_at(&self, point: Tuple) -> Color;
+}
+
+// custom sources can't be compared by value, so they are equal only to themselves
+impl PartialEq for dyn PatternSource {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+// templates can hold textures, custom sources and nested patterns, so they
+// can't be `Copy`. shared data sits behind an `Rc` to keep clones cheap.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Template {
     Test,
     Solid(Color),
-    Checkers(Color, Color),
-    Gradient(Color, Color),
-    Rings(Color, Color),
-    Stripe(Color, Color),
+    Checkers(Box<Pattern>, Box<Pattern>),
+    Gradient(Box<Pattern>, Box<Pattern>),
+    Rings(Box<Pattern>, Box<Pattern>),
+    Stripe(Box<Pattern>, Box<Pattern>),
+    // smooth gradients: outwards from the origin to a radius of 1, back and forth
+    // across bands in xz, and back and forth along x
+    RadialGradient(Box<Pattern>, Box<Pattern>),
+    RingGradient(Box<Pattern>, Box<Pattern>),
+    PingPongGradient(Box<Pattern>, Box<Pattern>),
+    // mixes the second pattern into the first by the given fraction
+    Blend(Box<Pattern>, Box<Pattern>, f64),
+    // noise driven mixes of two patterns
+    Fbm(Box<Pattern>, Box<Pattern>, Noise),
+    Turbulence(Box<Pattern>, Box<Pattern>, Noise),
+    // veins and growth rings distorted by turbulence of the given strength
+    Marble(Box<Pattern>, Box<Pattern>, Noise, f64),
+    Wood(Box<Pattern>, Box<Pattern>, Noise, f64),
+    // looks up the inner pattern at a point jittered by up to the given distance
+    Perturbed(Box<Pattern>, Noise, f64),
+    // with no mapping, the shape's own `uv_mapping` is used
+    Uv(UvTemplate, Option<Mapping>),
+    // one texture per face, in the order left, front, right, back, up, down
+    CubeMap(Rc<[UvTemplate; 6]>),
+    Custom(Rc<dyn PatternSource>),
 }
 
 impl Template {
     pub fn color_at(&self, point: Tuple) -> Color {
+        self.mapped_color_at(point, None)
+    }
+
+    // `mapping` is used by uv patterns that don't name their own
+    fn mapped_color_at(&self, point: Tuple, mapping: Option<Mapping>) -> Color {
         match self {
             Template::Solid(c) => *c,
             Template::Test => Color(point.x, point.y, point.z),
             Template::Checkers(a, b) => {
                 match (point.x.floor() + point.y.floor() + point.z.floor()) % 2.0 == 0.0 {
-                    true => *a,
-                    false => *b,
+                    true => a.mapped_color_at_object(point, mapping),
+                    false => b.mapped_color_at_object(point, mapping),
                 }
             }
             Template::Stripe(a, b) => match point.x.floor() % 2.0 == 0.0 {
-                true => *a,
-                false => *b,
+                true => a.mapped_color_at_object(point, mapping),
+                false => b.mapped_color_at_object(point, mapping),
             },
             Template::Gradient(a, b) => {
-                let distance = *b - *a;
+                let a = a.mapped_color_at_object(point, mapping);
+                let distance = b.mapped_color_at_object(point, mapping) - a;
                 let fraction = point.x - point.x.floor();
-                *a + distance * fraction
+                a + distance * fraction
             }
             Template::Rings(a, b) => {
                 match (point.x * point.x + point.z * point.z).sqrt().floor() % 2.0 == 0.0 {
-                    true => *a,
-                    false => *b,
+                    true => a.mapped_color_at_object(point, mapping),
+                    false => b.mapped_color_at_object(point, mapping),
                 }
             }
+            Template::RadialGradient(a, b) => {
+                let distance = (point.x * point.x + point.y * point.y + point.z * point.z).sqrt();
+                mix(a, b, point, mapping, distance)
+            }
+            Template::RingGradient(a, b) => {
+                let distance = (point.x * point.x + point.z * point.z).sqrt();
+                mix(a, b, point, mapping, triangle(distance))
+            }
+            Template::PingPongGradient(a, b) => mix(a, b, point, mapping, triangle(point.x)),
+            Template::Blend(a, b, mix) => {
+                a.mapped_color_at_object(point, mapping) * (1.0 - mix)
+                    + b.mapped_color_at_object(point, mapping) * *mix
+            }
+            Template::Fbm(a, b, noise) => mix(a, b, point, mapping, (noise.fbm(point) + 1.0) / 2.0),
+            Template::Turbulence(a, b, noise) => mix(a, b, point, mapping, noise.turbulence(point)),
+            Template::Marble(a, b, noise, strength) => {
+                let phase = (point.x + strength * noise.turbulence(point)) * PI;
+                mix(a, b, point, mapping, (phase.sin() + 1.0) / 2.0)
+            }
+            Template::Wood(a, b, noise, strength) => {
+                let radius = (point.x * point.x + point.z * point.z).sqrt();
+                let distorted = radius + strength * noise.fbm(point);
+                mix(a, b, point, mapping, distorted - distorted.floor())
+            }
+            Template::Perturbed(pattern, noise, scale) => {
+                let jitter = vector(
+                    noise.fbm(point),
+                    noise.fbm(point + vector(31.4, 0, 0)),
+                    noise.fbm(point + vector(0, 0, 27.1)),
+                );
+                pattern.mapped_color_at_object(point + jitter * *scale, mapping)
+            }
+            Template::Uv(texture, own) => {
+                // without a shape to ask, fall back to a spherical mapping
+                let (u, v) = own.or(mapping).unwrap_or(Mapping::Spherical).map(point);
+                texture.color_at(u, v)
+            }
+            Template::CubeMap(faces) => {
+                let (face, u, v) = cube_map(point);
+                let texture = match face {
+                    CubeFace::Left => &faces[0],
+                    CubeFace::Front => &faces[1],
+                    CubeFace::Right => &faces[2],
+                    CubeFace::Back => &faces[3],
+                    CubeFace::Up => &faces[4],
+                    CubeFace::Down => &faces[5],
+                };
+                texture.color_at(u, v)
+            }
+            Template::Custom(source) => source.color_at(point),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+fn mix(a: &Pattern, b: &Pattern, point: Tuple, mapping: Option<Mapping>, t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    a.mapped_color_at_object(point, mapping) * (1.0 - t)
+        + b.mapped_color_at_object(point, mapping) * t
+}
+
+// rises from 0 to 1 over [0, 1] and falls back over [1, 2], repeating
+fn triangle(x: f64) -> f64 {
+    1.0 - (x.rem_euclid(2.0) - 1.0).abs()
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Pattern {
     template: Template,
     transform: Matrix,
@@ -49,78 +158,212 @@ pub struct Pattern {
 }
 
 impl Pattern {
+    pub fn new<T: Into<Option<Matrix>>>(template: Template, transform: T) -> Pattern {
+        let m = match transform.into() {
+            Some(matrix) => matrix,
+            None => Matrix::identity(),
+        };
+        Pattern {
+            template,
+            transform: m,
+            inverse: m.inverse().unwrap(),
+        }
+    }
+
     pub fn color_at(&self, point: Tuple) -> Color {
         self.template.color_at(point)
     }
 
     pub fn color_at_object(&self, object_point: Tuple) -> Color {
+        self.mapped_color_at_object(object_point, None)
+    }
+
+    // like `color_at_object`, but uv patterns without a mapping of their own
+    // use the object's natural one
+    pub fn color_at_shape(&self, object: &dyn Object, object_point: Tuple) -> Color {
+        self.mapped_color_at_object(object_point, Some(object.uv_mapping()))
+    }
+
+    fn mapped_color_at_object(&self, object_point: Tuple, mapping: Option<Mapping>) -> Color {
         let pattern_point = self.inverse * object_point;
-        self.color_at(pattern_point)
+        self.template.mapped_color_at(pattern_point, mapping)
     }
 }
 
-pub fn stripe_pattern<T: Into<Option<Matrix>>>(
-    color_a: Color,
-    color_b: Color,
+impl From<Color> for Pattern {
+    fn from(c: Color) -> Pattern {
+        solid_pattern(c)
+    }
+}
+
+pub fn stripe_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
     transform: T,
 ) -> Pattern {
-    let m = match transform.into() {
-        Some(matrix) => matrix,
-        None => Matrix::identity(),
-    };
-    Pattern {
-        template: Template::Stripe(color_a, color_b),
-        transform: m,
-        inverse: m.inverse().unwrap(),
-    }
+    Pattern::new(
+        Template::Stripe(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
 }
 
-pub fn gradient_pattern<T: Into<Option<Matrix>>>(
-    color_a: Color,
-    color_b: Color,
+pub fn gradient_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
     transform: T,
 ) -> Pattern {
-    let m = match transform.into() {
-        Some(matrix) => matrix,
-        None => Matrix::identity(),
-    };
-    Pattern {
-        template: Template::Gradient(color_a, color_b),
-        transform: m,
-        inverse: m.inverse().unwrap(),
-    }
+    Pattern::new(
+        Template::Gradient(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
 }
 
-pub fn ring_pattern<T: Into<Option<Matrix>>>(
-    color_a: Color,
-    color_b: Color,
+pub fn ring_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
     transform: T,
 ) -> Pattern {
-    let m = match transform.into() {
-        Some(matrix) => matrix,
-        None => Matrix::identity(),
-    };
-    Pattern {
-        template: Template::Rings(color_a, color_b),
-        transform: m,
-        inverse: m.inverse().unwrap(),
-    }
+    Pattern::new(
+        Template::Rings(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
 }
 
-pub fn checkers_pattern<T: Into<Option<Matrix>>>(
-    color_a: Color,
-    color_b: Color,
+pub fn checkers_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
     transform: T,
 ) -> Pattern {
-    let m = match transform.into() {
-        Some(matrix) => matrix,
-        None => Matrix::identity(),
-    };
-    Pattern {
-        template: Template::Checkers(color_a, color_b),
-        transform: m,
-        inverse: m.inverse().unwrap(),
-    }
+    Pattern::new(
+        Template::Checkers(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
+}
+
+pub fn uv_pattern<M: Into<Option<Mapping>>, T: Into<Option<Matrix>>>(
+    texture: UvTemplate,
+    mapping: M,
+    transform: T,
+) -> Pattern {
+    Pattern::new(Template::Uv(texture, mapping.into()), transform)
+}
+
+pub fn cube_map_pattern<T: Into<Option<Matrix>>>(faces: [UvTemplate; 6], transform: T) -> Pattern {
+    Pattern::new(Template::CubeMap(Rc::new(faces)), transform)
+}
+
+pub fn radial_gradient_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::RadialGradient(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
+}
+
+pub fn ring_gradient_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::RingGradient(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
+}
+
+pub fn ping_pong_gradient_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::PingPongGradient(Box::new(a.into()), Box::new(b.into())),
+        transform,
+    )
+}
+
+pub fn blend_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    mix: f64,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::Blend(Box::new(a.into()), Box::new(b.into()), mix),
+        transform,
+    )
+}
+
+pub fn fbm_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    noise: Noise,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::Fbm(Box::new(a.into()), Box::new(b.into()), noise),
+        transform,
+    )
+}
+
+pub fn turbulence_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    noise: Noise,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::Turbulence(Box::new(a.into()), Box::new(b.into()), noise),
+        transform,
+    )
+}
+
+pub fn marble_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    noise: Noise,
+    strength: f64,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::Marble(Box::new(a.into()), Box::new(b.into()), noise, strength),
+        transform,
+    )
+}
+
+pub fn wood_pattern<A: Into<Pattern>, B: Into<Pattern>, T: Into<Option<Matrix>>>(
+    a: A,
+    b: B,
+    noise: Noise,
+    strength: f64,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::Wood(Box::new(a.into()), Box::new(b.into()), noise, strength),
+        transform,
+    )
+}
+
+pub fn perturbed_pattern<T: Into<Option<Matrix>>>(
+    pattern: Pattern,
+    noise: Noise,
+    scale: f64,
+    transform: T,
+) -> Pattern {
+    Pattern::new(
+        Template::Perturbed(Box::new(pattern), noise, scale),
+        transform,
+    )
+}
+
+pub fn custom_pattern<P: PatternSource + 'static, T: Into<Option<Matrix>>>(
+    source: P,
+    transform: T,
+) -> Pattern {
+    Pattern::new(Template::Custom(Rc::new(source)), transform)
 }
 
 pub fn solid_pattern(c: Color) -> Pattern {
@@ -147,10 +390,13 @@ pub fn test_pattern<T: Into<Option<Matrix>>>(transform: T) -> Pattern {
 mod tests {
     use super::*;
     use crate::color::{color, BLACK, WHITE};
+    use crate::mapping::uv_checkers;
     use crate::object::Object;
-    use crate::shapes::Sphere;
-    use crate::transformations::{scale, translate};
+    use crate::shapes::{Cube, Plane, Sphere};
+    use crate::texture::{Filter, Texture, Wrap};
+    use crate::transformations::{rotate_y, scale, translate};
     use crate::tuple::point;
+    use std::f64::consts::PI;
 
     #[test]
     fn stripe_pattern_is_constant_in_y() {
@@ -267,4 +513,313 @@ mod tests {
         assert!(pattern.color_at(point(0, 0, 0)) == BLACK);
         assert!(pattern.color_at(point(9, 1, 10)) == BLACK);
     }
+
+    #[test]
+    fn texture_map_with_spherical_mapping() {
+        let checkers = uv_checkers(16, 8, BLACK, WHITE);
+        let pattern = uv_pattern(checkers, Mapping::Spherical, None);
+
+        let expected = [
+            (point(0.4315, 0.4670, 0.7719), WHITE),
+            (point(-0.9654, 0.2552, -0.0534), BLACK),
+            (point(0.1039, 0.7090, 0.6975), WHITE),
+            (point(-0.4986, -0.7856, -0.3663), BLACK),
+            (point(-0.0317, -0.9395, 0.3411), BLACK),
+            (point(0.4809, -0.7721, 0.4154), BLACK),
+            (point(0.0285, -0.9612, -0.2745), BLACK),
+            (point(-0.5734, -0.2162, -0.7903), WHITE),
+            (point(0.7688, -0.1470, 0.6223), BLACK),
+            (point(-0.7652, 0.2175, 0.6060), BLACK),
+        ];
+
+        for (p, c) in expected.iter() {
+            assert!(pattern.color_at(*p) == *c);
+        }
+    }
+
+    #[test]
+    fn texture_map_respects_pattern_transformation() {
+        let checkers = uv_checkers(2, 2, BLACK, WHITE);
+        let pattern = uv_pattern(checkers, Mapping::Planar, scale(2, 2, 2));
+
+        assert!(pattern.color_at_object(point(0.5, 0, 0.5)) == BLACK);
+        assert!(pattern.color_at_object(point(1.5, 0, 0.5)) == WHITE);
+    }
+
+    #[test]
+    fn shapes_expose_their_natural_mapping() {
+        let sphere = Sphere::default();
+        let cube = Cube::default();
+        let plane = Plane::default();
+
+        assert!(sphere.uv_mapping() == Mapping::Spherical);
+        assert!(cube.uv_mapping() == Mapping::Cubic);
+        assert!(plane.uv_mapping() == Mapping::Planar);
+    }
+
+    #[test]
+    fn uv_patterns_without_a_mapping_use_the_shapes() {
+        let sphere = Sphere::default();
+        let plane = Plane::default();
+        let p = point(0.25, 0, 0.25);
+
+        let pattern = uv_pattern(uv_checkers(2, 2, BLACK, WHITE), None, None);
+        assert!(pattern.color_at_shape(&plane, p) == BLACK);
+        assert!(pattern.color_at_shape(&sphere, p) == WHITE);
+        assert!(pattern.color_at_object(p) == WHITE);
+
+        let planar = uv_pattern(uv_checkers(2, 2, BLACK, WHITE), Mapping::Planar, None);
+        assert!(planar.color_at_shape(&sphere, p) == BLACK);
+    }
+
+    #[test]
+    fn finding_colors_on_a_mapped_cube() {
+        let red = color(1, 0, 0);
+        let yellow = color(1, 1, 0);
+        let brown = color(1, 0.5, 0);
+        let green = color(0, 1, 0);
+        let cyan = color(0, 1, 1);
+        let blue = color(0, 0, 1);
+        let purple = color(1, 0, 1);
+        let white = color(1, 1, 1);
+
+        let left = UvTemplate::AlignCheck(yellow, cyan, red, blue, brown);
+        let front = UvTemplate::AlignCheck(cyan, red, yellow, brown, green);
+        let right = UvTemplate::AlignCheck(red, yellow, purple, green, white);
+        let back = UvTemplate::AlignCheck(green, purple, cyan, white, blue);
+        let up = UvTemplate::AlignCheck(brown, cyan, purple, red, yellow);
+        let down = UvTemplate::AlignCheck(purple, brown, green, blue, white);
+        let pattern = cube_map_pattern([left, front, right, back, up, down], None);
+
+        let expected = [
+            (point(-1, 0, 0), yellow),
+            (point(-1, 0.9, -0.9), cyan),
+            (point(-1, 0.9, 0.9), red),
+            (point(-1, -0.9, -0.9), blue),
+            (point(-1, -0.9, 0.9), brown),
+            (point(0, 0, 1), cyan),
+            (point(-0.9, 0.9, 1), red),
+            (point(0.9, 0.9, 1), yellow),
+            (point(-0.9, -0.9, 1), brown),
+            (point(0.9, -0.9, 1), green),
+            (point(1, 0, 0), red),
+            (point(1, 0.9, 0.9), yellow),
+            (point(1, 0.9, -0.9), purple),
+            (point(1, -0.9, 0.9), green),
+            (point(1, -0.9, -0.9), white),
+            (point(0, 0, -1), green),
+            (point(0.9, 0.9, -1), purple),
+            (point(-0.9, 0.9, -1), cyan),
+            (point(0.9, -0.9, -1), white),
+            (point(-0.9, -0.9, -1), blue),
+            (point(0, 1, 0), brown),
+            (point(-0.9, 1, -0.9), cyan),
+            (point(0.9, 1, -0.9), purple),
+            (point(-0.9, 1, 0.9), red),
+            (point(0.9, 1, 0.9), yellow),
+            (point(0, -1, 0), purple),
+            (point(-0.9, -1, 0.9), brown),
+            (point(0.9, -1, 0.9), green),
+            (point(-0.9, -1, -0.9), blue),
+            (point(0.9, -1, -0.9), white),
+        ];
+
+        for (p, c) in expected.iter() {
+            assert!(pattern.color_at(*p) == *c);
+        }
+    }
+
+    #[test]
+    fn image_texture_on_a_plane() {
+        let mut image = crate::canvas::Canvas::new(2, 1);
+        image.write_pixel(0, 0, color(1, 0, 0));
+        image.write_pixel(1, 0, color(0, 0, 1));
+        let texture = Texture::new(image, Filter::Nearest, Wrap::Repeat);
+        let pattern = uv_pattern(UvTemplate::Image(texture), Mapping::Planar, None);
+
+        assert!(pattern.color_at(point(0.25, 0, 0.5)) == color(1, 0, 0));
+        assert!(pattern.color_at(point(0.75, 0, 0.5)) == color(0, 0, 1));
+        assert!(pattern.color_at(point(1.25, 0, 0.5)) == color(1, 0, 0));
+    }
+
+    #[derive(Debug)]
+    struct Diagonal;
+
+    impl PatternSource for Diagonal {
+        fn color_at(&self, point: Tuple) -> Color {
+            match (point.x + point.z).floor() % 2.0 == 0.0 {
+                true => WHITE,
+                false => BLACK,
+            }
+        }
+    }
+
+    #[test]
+    fn custom_pattern_sources_are_evaluated_in_pattern_space() {
+        let pattern = custom_pattern(Diagonal, scale(2, 2, 2));
+
+        assert!(pattern.color_at(point(0.5, 0, 0.6)) == BLACK);
+        assert!(pattern.color_at_object(point(0.5, 0, 0.6)) == WHITE);
+        assert!(pattern.color_at_object(point(1.5, 0, 0.6)) == BLACK);
+    }
+
+    #[test]
+    fn custom_patterns_are_only_equal_to_themselves() {
+        let a = custom_pattern(Diagonal, None);
+        let b = custom_pattern(Diagonal, None);
+
+        assert!(a == a.clone());
+        assert!(a != b);
+    }
+
+    #[test]
+    fn materials_accept_custom_patterns() {
+        let mut object = Sphere::default();
+        object.material.pattern = custom_pattern(Diagonal, None);
+
+        let c = object.material().pattern.color_at_object(point(1.5, 0, 0));
+        assert!(c == BLACK);
+    }
+
+    #[test]
+    fn stripes_of_nested_patterns() {
+        let pattern = stripe_pattern(checkers_pattern(WHITE, BLACK, None), color(1, 0, 0), None);
+
+        assert!(pattern.color_at(point(0.5, 0, 0.5)) == WHITE);
+        assert!(pattern.color_at(point(0.5, 0, 1.5)) == BLACK);
+        assert!(pattern.color_at(point(1.5, 0, 0.5)) == color(1, 0, 0));
+        assert!(pattern.color_at(point(1.5, 0, 1.5)) == color(1, 0, 0));
+    }
+
+    #[test]
+    fn nested_patterns_have_their_own_transformation() {
+        let inner = stripe_pattern(WHITE, BLACK, scale(0.5, 1, 1));
+        let pattern = checkers_pattern(inner, color(1, 0, 0), scale(2, 2, 2));
+
+        assert!(pattern.color_at_object(point(0.2, 0, 0)) == WHITE);
+        assert!(pattern.color_at_object(point(1.2, 0, 0)) == BLACK);
+        assert!(pattern.color_at_object(point(2.2, 0, 0)) == color(1, 0, 0));
+    }
+
+    #[test]
+    fn gradient_between_nested_patterns() {
+        let pattern = gradient_pattern(stripe_pattern(WHITE, BLACK, scale(0.5, 1, 1)), BLACK, None);
+
+        assert!(pattern.color_at(point(0.25, 0, 0)) == color(0.75, 0.75, 0.75));
+        assert!(pattern.color_at(point(0.75, 0, 0)) == BLACK);
+    }
+
+    #[test]
+    fn blending_averages_two_patterns() {
+        let a = stripe_pattern(WHITE, BLACK, None);
+        let b = stripe_pattern(WHITE, BLACK, rotate_y(PI / 2.0));
+        let pattern = blend_pattern(a, b, 0.5, None);
+
+        assert!(pattern.color_at(point(0.5, 0, -0.5)) == WHITE);
+        assert!(pattern.color_at(point(1.5, 0, -0.5)) == color(0.5, 0.5, 0.5));
+        assert!(pattern.color_at(point(1.5, 0, 0.5)) == BLACK);
+    }
+
+    #[test]
+    fn blend_mixes_by_the_given_fraction() {
+        let pattern = blend_pattern(WHITE, color(1, 0, 0), 0.25, None);
+
+        assert!(pattern.color_at(point(0, 0, 0)) == color(1, 0.75, 0.75));
+    }
+
+    #[test]
+    fn noise_patterns_mix_between_their_inputs() {
+        let noise = Noise::perlin(1);
+        let patterns = [
+            fbm_pattern(WHITE, BLACK, noise.clone(), None),
+            turbulence_pattern(WHITE, BLACK, noise.clone(), None),
+            marble_pattern(WHITE, BLACK, noise.clone(), 5.0, None),
+            wood_pattern(WHITE, BLACK, noise, 0.5, None),
+        ];
+
+        for pattern in patterns.iter() {
+            for i in 0..20 {
+                let c = pattern.color_at(point(i as f64 * 0.31, 0.2, i as f64 * 0.17));
+                assert!(c.0 >= 0.0 && c.0 <= 1.0);
+                assert!(c.0 == c.1 && c.1 == c.2);
+            }
+        }
+    }
+
+    #[test]
+    fn noise_patterns_are_reproducible() {
+        let a = marble_pattern(WHITE, BLACK, Noise::simplex(4), 2.0, None);
+        let b = marble_pattern(WHITE, BLACK, Noise::simplex(4), 2.0, None);
+        let p = point(0.7, 1.3, -2.1);
+
+        assert!(a == b);
+        assert!(a.color_at(p) == b.color_at(p));
+    }
+
+    #[test]
+    fn wood_without_distortion_is_concentric_rings() {
+        let pattern = wood_pattern(WHITE, BLACK, Noise::perlin(1), 0.0, None);
+
+        assert!(pattern.color_at(point(0.25, 0, 0)) == color(0.75, 0.75, 0.75));
+        assert!(pattern.color_at(point(0, 5, 1.25)) == color(0.75, 0.75, 0.75));
+    }
+
+    #[test]
+    fn perturbing_a_pattern_jitters_its_lookup_point() {
+        let stripes = stripe_pattern(WHITE, BLACK, scale(0.1, 1, 1));
+        let still = perturbed_pattern(stripes.clone(), Noise::perlin(8), 0.0, None);
+        let jittered = perturbed_pattern(stripes.clone(), Noise::perlin(8), 0.5, None);
+
+        let points: Vec<Tuple> = (0..50)
+            .map(|i| point(i as f64 * 0.13 + 0.05, 0.3, 0.4))
+            .collect();
+
+        assert!(points
+            .iter()
+            .all(|p| still.color_at(*p) == stripes.color_at_object(*p)));
+        assert!(points
+            .iter()
+            .any(|p| jittered.color_at(*p) != stripes.color_at_object(*p)));
+    }
+
+    #[test]
+    fn radial_gradient_blends_outwards_from_the_origin() {
+        let pattern = radial_gradient_pattern(WHITE, BLACK, None);
+
+        assert!(pattern.color_at(point(0, 0, 0)) == WHITE);
+        assert!(pattern.color_at(point(0, 0.25, 0)) == color(0.75, 0.75, 0.75));
+        assert!(pattern.color_at(point(0.3, 0, 0.4)) == color(0.5, 0.5, 0.5));
+        assert!(pattern.color_at(point(0, 0, 1)) == BLACK);
+        assert!(pattern.color_at(point(3, 0, 0)) == BLACK);
+    }
+
+    #[test]
+    fn ring_gradient_blends_between_bands() {
+        let pattern = ring_gradient_pattern(WHITE, BLACK, None);
+
+        assert!(pattern.color_at(point(0, 0, 0)) == WHITE);
+        assert!(pattern.color_at(point(0.5, 0, 0)) == color(0.5, 0.5, 0.5));
+        assert!(pattern.color_at(point(0, 0, 1)) == BLACK);
+        assert!(pattern.color_at(point(0.9, 0, 1.2)) == color(0.5, 0.5, 0.5));
+        assert!(pattern.color_at(point(2, 0, 0)) == WHITE);
+    }
+
+    #[test]
+    fn ping_pong_gradient_has_no_seam_at_integers() {
+        let pattern = ping_pong_gradient_pattern(WHITE, BLACK, None);
+
+        assert!(pattern.color_at(point(0.25, 0, 0)) == color(0.75, 0.75, 0.75));
+        assert!(pattern.color_at(point(0.999, 0, 0)) == pattern.color_at(point(1.001, 0, 0)));
+        assert!(pattern.color_at(point(1, 0, 0)) == BLACK);
+        assert!(pattern.color_at(point(1.25, 0, 0)) == color(0.25, 0.25, 0.25));
+        assert!(pattern.color_at(point(-0.25, 0, 0)) == color(0.75, 0.75, 0.75));
+    }
+
+    #[test]
+    fn smooth_gradients_respect_pattern_transformation() {
+        let pattern = ping_pong_gradient_pattern(WHITE, BLACK, scale(2, 2, 2));
+
+        assert!(pattern.color_at_object(point(1, 0, 0)) == color(0.5, 0.5, 0.5));
+        assert!(pattern.color_at_object(point(2, 0, 0)) == BLACK);
+    }
 }
@@ -0,0 +1,309 @@
+use super::canvas::Canvas;
+use super::color::{Color, BLACK};
+use super::texture::{Filter, Texture, Wrap};
+use super::tonemap::ToneMap;
+use std::fmt::Debug;
+
+// an image-space effect applied to a linear render
+pub trait PostEffect: Debug {
+    fn apply(&self, image: &Canvas) -> Canvas;
+}
+
+// a chain of effects run in order, e.g. bloom and vignette on the HDR
+// values followed by a tone map
+#[derive(Debug, Default)]
+pub struct PostProcess {
+    effects: Vec<Box<dyn PostEffect>>,
+}
+
+impl PostProcess {
+    pub fn new() -> PostProcess {
+        PostProcess::default()
+    }
+
+    pub fn then<E: PostEffect + 'static>(mut self, effect: E) -> PostProcess {
+        self.effects.push(Box::new(effect));
+        self
+    }
+
+    pub fn apply(&self, image: &Canvas) -> Canvas {
+        self.effects
+            .iter()
+            .fold(image.clone(), |image, effect| effect.apply(&image))
+    }
+}
+
+impl PostEffect for ToneMap {
+    fn apply(&self, image: &Canvas) -> Canvas {
+        ToneMap::apply(self, image)
+    }
+}
+
+// light above the threshold is blurred and added back, so highlights glow
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bloom {
+    pub threshold: f64,
+    pub radius: usize,
+    pub intensity: f64,
+}
+
+impl Bloom {
+    pub fn new(threshold: f64, radius: usize, intensity: f64) -> Bloom {
+        Bloom {
+            threshold,
+            radius,
+            intensity,
+        }
+    }
+}
+
+// a separable gaussian blur, with edges clamped
+fn blur(image: &Canvas, radius: usize) -> Canvas {
+    let sigma = (radius as f64 / 2.0).max(0.5);
+    let kernel: Vec<f64> = (0..=2 * radius)
+        .map(|i| {
+            let d = i as f64 - radius as f64;
+            (-d * d / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let total: f64 = kernel.iter().sum();
+
+    let pass = |image: &Canvas, horizontal: bool| {
+        let mut out = Canvas::new(image.width, image.height);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let mut sum = BLACK;
+                for (i, weight) in kernel.iter().enumerate() {
+                    let offset = i as isize - radius as isize;
+                    let (sx, sy) = match horizontal {
+                        true => (
+                            (x as isize + offset).clamp(0, image.width as isize - 1) as usize,
+                            y,
+                        ),
+                        false => (
+                            x,
+                            (y as isize + offset).clamp(0, image.height as isize - 1) as usize,
+                        ),
+                    };
+                    sum = sum + *image.get_pixel(sx, sy) * *weight;
+                }
+                out.write_pixel(x, y, sum * (1.0 / total));
+            }
+        }
+        out
+    };
+
+    pass(&pass(image, true), false)
+}
+
+impl PostEffect for Bloom {
+    fn apply(&self, image: &Canvas) -> Canvas {
+        let mut bright = image.clone();
+        for pixel in bright.pixels.iter_mut() {
+            *pixel = Color(
+                (pixel.0 - self.threshold).max(0.0),
+                (pixel.1 - self.threshold).max(0.0),
+                (pixel.2 - self.threshold).max(0.0),
+            );
+        }
+        let glow = blur(&bright, self.radius);
+
+        let mut out = image.clone();
+        for (pixel, glow) in out.pixels.iter_mut().zip(glow.pixels.iter()) {
+            *pixel = *pixel + *glow * self.intensity;
+        }
+        out
+    }
+}
+
+// darkens the image towards its corners. the distance from the centre is
+// measured as a fraction of the half diagonal, so corners are at 1.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Vignette {
+    pub strength: f64,
+    pub falloff: f64,
+}
+
+impl Vignette {
+    pub fn new(strength: f64, falloff: f64) -> Vignette {
+        Vignette { strength, falloff }
+    }
+}
+
+impl PostEffect for Vignette {
+    fn apply(&self, image: &Canvas) -> Canvas {
+        let (cx, cy) = (image.width as f64 / 2.0, image.height as f64 / 2.0);
+        let half_diagonal = (cx * cx + cy * cy).sqrt();
+
+        let mut out = image.clone();
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let dx = x as f64 + 0.5 - cx;
+                let dy = y as f64 + 0.5 - cy;
+                let r = (dx * dx + dy * dy).sqrt() / half_diagonal;
+                let factor = (1.0 - self.strength * r.powf(self.falloff)).max(0.0);
+                out.write_pixel(x, y, *image.get_pixel(x, y) * factor);
+            }
+        }
+        out
+    }
+}
+
+// lateral chromatic aberration: red is magnified and blue shrunk about the
+// centre of the frame by the given fraction, so colours fringe at the edges.
+// the strength must be in [0, 1): at 1 blue would shrink to nothing, so it
+// can only be set through `new`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChromaticAberration {
+    strength: f64,
+}
+
+impl ChromaticAberration {
+    pub fn new(strength: f64) -> ChromaticAberration {
+        assert!(
+            (0.0..1.0).contains(&strength),
+            "chromatic aberration strength {} is outside [0, 1)",
+            strength
+        );
+        ChromaticAberration { strength }
+    }
+
+    pub fn strength(&self) -> f64 {
+        self.strength
+    }
+}
+
+impl PostEffect for ChromaticAberration {
+    fn apply(&self, image: &Canvas) -> Canvas {
+        let texture = Texture::new(image.clone(), Filter::Bilinear, Wrap::Clamp);
+        let (width, height) = (image.width as f64, image.height as f64);
+
+        // a larger image samples closer to the centre
+        let sample = |u: f64, v: f64, scale: f64| {
+            texture.color_at(0.5 + (u - 0.5) / scale, 0.5 + (v - 0.5) / scale)
+        };
+
+        let mut out = image.clone();
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let u = (x as f64 + 0.5) / width;
+                let v = 1.0 - (y as f64 + 0.5) / height;
+                let red = sample(u, v, 1.0 + self.strength).0;
+                let green = image.get_pixel(x, y).1;
+                let blue = sample(u, v, 1.0 - self.strength).2;
+                out.write_pixel(x, y, Color(red, green, blue));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{color, WHITE};
+    use crate::image::Gamma;
+    use crate::tonemap::ToneMapOperator;
+
+    fn filled(width: usize, height: usize, c: Color) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for pixel in canvas.pixels.iter_mut() {
+            *pixel = c;
+        }
+        canvas
+    }
+
+    #[test]
+    fn bloom_ignores_light_below_the_threshold() {
+        let image = filled(5, 5, color(0.5, 0.9, 1));
+
+        assert!(Bloom::new(1.0, 2, 1.0).apply(&image) == image);
+    }
+
+    #[test]
+    fn bloom_spreads_bright_pixels_into_their_neighbours() {
+        let mut image = Canvas::new(9, 9);
+        image.write_pixel(4, 4, color(11, 1, 1));
+
+        let bloomed = Bloom::new(1.0, 2, 1.0).apply(&image);
+
+        assert!(bloomed.get_pixel(5, 4).0 > 0.0);
+        assert!(bloomed.get_pixel(5, 4).1 == 0.0);
+        assert!(bloomed.get_pixel(0, 0) == &BLACK);
+        assert!(bloomed.get_pixel(4, 4).0 > 11.0);
+    }
+
+    #[test]
+    fn blurring_preserves_a_flat_image() {
+        let image = filled(4, 3, color(0.3, 2, 0.1));
+
+        assert!(blur(&image, 3) == image);
+    }
+
+    #[test]
+    fn vignette_darkens_corners_more_than_the_centre() {
+        let image = filled(11, 11, WHITE);
+
+        let vignetted = Vignette::new(0.5, 2.0).apply(&image);
+
+        assert!(vignetted.get_pixel(5, 5) == &WHITE);
+        let corner = vignetted.get_pixel(0, 0).0;
+        assert!(corner < 0.6 && corner > 0.5);
+    }
+
+    #[test]
+    fn chromatic_aberration_leaves_the_centre_alone() {
+        let mut image = filled(9, 9, color(0.2, 0.2, 0.2));
+        image.write_pixel(4, 4, color(1, 1, 1));
+
+        let fringed = ChromaticAberration::new(0.1).apply(&image);
+
+        assert!(fringed.get_pixel(4, 4) == &color(1, 1, 1));
+    }
+
+    #[test]
+    fn chromatic_aberration_splits_red_and_blue_at_the_edges() {
+        let mut image = Canvas::new(21, 1);
+        image.write_pixel(18, 0, WHITE);
+
+        let fringed = ChromaticAberration::new(0.2).apply(&image);
+
+        // red is magnified outwards, blue pulled in
+        assert!(fringed.get_pixel(18, 0).1 == 1.0);
+        assert!(fringed.get_pixel(20, 0).0 > 0.0);
+        assert!(fringed.get_pixel(20, 0).2 == 0.0);
+        assert!(fringed.get_pixel(16, 0).2 > 0.0);
+        assert!(fringed.get_pixel(16, 0).0 == 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "chromatic aberration strength 1 is outside [0, 1)")]
+    fn chromatic_aberration_rejects_a_strength_of_one() {
+        ChromaticAberration::new(1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "chromatic aberration strength -0.1 is outside [0, 1)")]
+    fn chromatic_aberration_rejects_a_negative_strength() {
+        ChromaticAberration::new(-0.1);
+    }
+
+    #[test]
+    fn chromatic_aberration_strength_can_be_read_back() {
+        assert!(ChromaticAberration::new(0.25).strength() == 0.25);
+    }
+
+    #[test]
+    fn post_process_runs_effects_in_order() {
+        let image = filled(3, 3, color(2, 0.5, 0));
+        let tonemap = ToneMap::new(0.0, ToneMapOperator::Clamp, Gamma::Linear);
+
+        let processed = PostProcess::new()
+            .then(Vignette::new(0.0, 2.0))
+            .then(tonemap)
+            .apply(&image);
+
+        assert!(processed == filled(3, 3, color(1, 0.5, 0)));
+        assert!(PostProcess::new().apply(&image) == image);
+    }
+}
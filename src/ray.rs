@@ -4,11 +4,31 @@ use crate::tuple::*;
 pub struct Ray {
     pub origin: Tuple,
     pub direction: Tuple,
+    // in nanometres, only set when rendering spectrally
+    pub wavelength: Option<f64>,
+    // when during the shutter interval the ray was cast, for motion blur
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Tuple, direction: Tuple) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            wavelength: None,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_wavelength<T: Into<Option<f64>>>(self, wavelength: T) -> Ray {
+        Ray {
+            wavelength: wavelength.into(),
+            ..self
+        }
+    }
+
+    pub fn with_time(self, time: f64) -> Ray {
+        Ray { time, ..self }
     }
 
     pub fn position(&self, t: f64) -> Tuple {
@@ -19,6 +39,8 @@ impl Ray {
         Ray {
             origin: m * self.origin,
             direction: m * self.direction,
+            wavelength: self.wavelength,
+            time: self.time,
         }
     }
 }
@@ -74,4 +96,29 @@ mod tests {
         assert!(r2.origin == point(2.0, 6.0, 12.0));
         assert!(r2.direction == vector(0.0, 3.0, 0.0));
     }
+
+    #[test]
+    fn rays_have_no_wavelength_by_default() {
+        let r = ray(point(1, 2, 3), vector(4, 5, 6));
+
+        assert!(r.wavelength == None);
+    }
+
+    #[test]
+    fn transforming_a_ray_keeps_its_wavelength() {
+        let r = ray(point(1, 2, 3), vector(0, 1, 0)).with_wavelength(550.0);
+        let r2 = r.transform(translate(3, 4, 5));
+
+        assert!(r2.wavelength == Some(550.0));
+    }
+
+    #[test]
+    fn transforming_a_ray_keeps_its_time() {
+        let r = ray(point(1, 2, 3), vector(0, 1, 0)).with_time(0.25);
+        let r2 = r.transform(translate(3, 4, 5));
+
+        assert!(r.time == 0.25);
+        assert!(r2.time == 0.25);
+        assert!(ray(point(1, 2, 3), vector(0, 1, 0)).time == 0.0);
+    }
 }
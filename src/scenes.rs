@@ -0,0 +1,555 @@
+// the example scenes rendered by the programs in src/bin. they live here so
+// that tests can render them small and check them against reference images.
+use crate::animation::{Easing, Track};
+use crate::camera::Camera;
+use crate::color::{color, BLACK, WHITE};
+use crate::light::PointLight;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::object::Object;
+use crate::pattern::{checkers_pattern, gradient_pattern, solid_pattern, stripe_pattern};
+use crate::shapes::{Cube, Plane, Sphere};
+use crate::transformations::{rotate_x, rotate_y, scale, translate, view_transform};
+use crate::tuple::{point, vector};
+use crate::world::World;
+use rand::Rng;
+use std::f64::consts::PI;
+
+pub fn cubes(width: usize, height: usize) -> (Camera, World) {
+    let mut floor = Plane::default();
+    let mut floor_material = Material::default();
+    floor_material.pattern = checkers_pattern(WHITE, BLACK, None);
+    floor_material.reflective = 0.4;
+    floor_material.diffuse = 0.7;
+    floor_material.specular = 0.3;
+    floor.material = floor_material;
+
+    let mut brick1 = Cube::default();
+    brick1.transform(scale(0.5, 0.25, 1.5).translate(-1.0, 0.25, 1.0));
+    let mut brick_material = Material::default();
+    brick_material.pattern = solid_pattern(color(0.3, 0, 0));
+    brick_material.diffuse = 0.7;
+    brick_material.ambient = 0.8;
+    brick_material.specular = 0.7;
+    brick_material.shininess = 90.0;
+    brick1.material = brick_material;
+
+    let mut brick2 = brick1.clone();
+    brick2.transform(translate(1.1, 0.0, 0.0));
+
+    let mut brick3 = brick2.clone();
+    brick3.transform(translate(1.1, 0.0, 0.0));
+
+    let mut brick4 = brick1.clone();
+    brick4.transform(translate(0.0, 0.5, 0.0).rotate_y(180.0));
+
+    let world = World::new(
+        vec![
+            Box::new(brick1),
+            Box::new(brick2),
+            Box::new(brick3),
+            Box::new(brick4),
+            Box::new(floor),
+        ],
+        vec![PointLight::new(point(-10, 10, -10), color(1, 1, 1))],
+    );
+
+    let camera = Camera::new(
+        width,
+        height,
+        PI / 3.0,
+        view_transform(point(0, 1.5, -5), point(0, 1, 0), vector(0, 1, 0)),
+    );
+
+    (camera, world)
+}
+
+enum RowDirection {
+    X,
+    Z,
+}
+
+enum RowType {
+    Full,
+    Middle,
+    Edges,
+}
+
+fn gen_row_positions<R: Rng>(rng: &mut R) -> Vec<i32> {
+    let num = rng.gen_range(1, 5);
+
+    let row_type: RowType;
+    if num <= 2 {
+        row_type = RowType::Edges;
+    } else if num == 3 {
+        row_type = RowType::Full;
+    } else {
+        row_type = RowType::Middle;
+    }
+
+    match row_type {
+        RowType::Full => vec![0, 1, 2],
+        RowType::Middle => vec![1],
+        RowType::Edges => vec![0, 2],
+    }
+}
+
+fn build_row<R: Rng>(
+    of: &Cube,
+    direction: RowDirection,
+    level: i32,
+    rng: &mut R,
+) -> Vec<Box<dyn Object>> {
+    let positions = gen_row_positions(rng);
+    let mut row: Vec<Box<dyn Object>> = vec![];
+
+    for i in positions {
+        let mut new_block = of.clone();
+        let x_pos = match direction {
+            RowDirection::X => 0.0,
+            RowDirection::Z => i as f64 * 1.1,
+        };
+
+        let y_pos = level as f64 * 0.5;
+        let z_pos = match direction {
+            RowDirection::X => i as f64 * 1.1,
+            RowDirection::Z => 0.0,
+        };
+
+        new_block.transform(translate(x_pos, y_pos, z_pos));
+        row.push(Box::new(new_block))
+    }
+
+    row
+}
+
+// a tower of blocks with a random block or two missing from each row
+pub fn jenga<R: Rng>(width: usize, height: usize, rng: &mut R) -> (Camera, World) {
+    let floor_material = Material {
+        ambient: 0.1,
+        diffuse: 0.7,
+        specular: 0.3,
+        shininess: 250.0,
+        reflective: 0.2,
+        transparency: 0.0,
+        refractive_index: 0.0,
+        pattern: checkers_pattern(WHITE, BLACK, None),
+        ..Material::default()
+    };
+    let floor = Plane::new(floor_material, Matrix::identity());
+
+    let mut block_x_material = Material::default();
+    block_x_material.pattern = solid_pattern(color(0.4, 0, 0));
+    block_x_material.diffuse = 0.7;
+    block_x_material.ambient = 0.7;
+    block_x_material.specular = 0.4;
+    block_x_material.reflective = 0.01;
+    block_x_material.shininess = 200.0;
+
+    let mut block_x = Cube::default();
+    block_x.transform(
+        scale(0.5, 0.25, 1.6)
+            .rotate_y(PI / 2.0)
+            .translate(0.1, 0.25, -0.6),
+    );
+    block_x.material = block_x_material.clone();
+
+    let mut block_z_material = block_x_material;
+    block_z_material.pattern = solid_pattern(color(0, 0.4, 0));
+
+    let mut block_z = Cube::default();
+    block_z.transform(scale(0.5, 0.25, 1.6).translate(-1.0, 0.25, 0.5));
+    block_z.material = block_z_material;
+
+    let mut objects: Vec<Box<dyn Object>> = vec![Box::new(floor)];
+
+    for i in 0..21 {
+        match i % 2 == 0 {
+            false => {
+                let row = &mut build_row(&block_z, RowDirection::Z, i, rng);
+                objects.append(row);
+            }
+            true => {
+                let row = &mut build_row(&block_x, RowDirection::X, i, rng);
+                objects.append(row)
+            }
+        };
+    }
+
+    let world = World {
+        objects,
+        light_sources: vec![PointLight::new(point(9.5, 5.0, -14), color(1, 1, 1))],
+    };
+
+    let camera = Camera::new(
+        width,
+        height,
+        PI / 3.0,
+        view_transform(point(8.5, 1.0, -4), point(0, 5.5, 0), vector(0, 1, 0)),
+    );
+
+    (camera, world)
+}
+
+// glass and solid spheres in a room with striped walls
+pub fn reflect_refract(width: usize, height: usize) -> (Camera, World) {
+    let wall_material = Material {
+        ambient: 0.0,
+        diffuse: 0.4,
+        specular: 0.0,
+        pattern: stripe_pattern(
+            color(0.45, 0.45, 0.45),
+            color(0.55, 0.55, 0.55),
+            scale(0.25, 0.25, 0.25).rotate_y(PI / 2.0),
+        ),
+        shininess: 200.0,
+        reflective: 0.0,
+        transparency: 0.0,
+        refractive_index: 1.0,
+        ..Material::default()
+    };
+
+    let mut floor_material = Material::default();
+    floor_material.pattern =
+        checkers_pattern(color(0.35, 0.35, 0.35), color(0.65, 0.65, 0.65), None);
+    floor_material.specular = 0.0;
+    floor_material.reflective = 0.4;
+
+    let floor = Plane::new(floor_material, rotate_y(0.31415));
+
+    let mut ceiling_material = Material::default();
+    ceiling_material.pattern = solid_pattern(color(0.8, 0.8, 0.8));
+    ceiling_material.specular = 0.0;
+    ceiling_material.ambient = 0.3;
+    let ceiling = Plane::new(ceiling_material, translate(0, 5, 0));
+
+    let west_wall = Plane::new(
+        wall_material.clone(),
+        rotate_y(PI / 2.0)
+            .rotate_z(PI / 2.0)
+            .translate(-5.0, 0.0, 0.0),
+    );
+    let east_wall = Plane::new(
+        wall_material.clone(),
+        rotate_y(-PI / 2.0)
+            .rotate_z(-PI / 2.0)
+            .translate(5.0, 0.0, 0.0),
+    );
+
+    let north_wall = Plane::new(
+        wall_material.clone(),
+        rotate_x(PI / 2.0).translate(0.0, 0.0, 5.0),
+    );
+
+    let south_wall = Plane::new(wall_material, rotate_x(PI / 2.0).translate(0.0, 0.0, -5.0));
+
+    let mut red_sphere = Sphere::default();
+    red_sphere.transform(translate(-0.6, 1, 0.6));
+    red_sphere.material.pattern = solid_pattern(color(1, 0.3, 0.2));
+    red_sphere.material.specular = 0.4;
+    red_sphere.material.shininess = 5.0;
+
+    let mut blue_glass_sphere = Sphere::default();
+    blue_glass_sphere.transform(scale(0.7, 0.7, 0.7).translate(0.6, 0.7, -0.6));
+    blue_glass_sphere.material.pattern = solid_pattern(color(0, 0, 0.2));
+    blue_glass_sphere.material.ambient = 0.0;
+    blue_glass_sphere.material.diffuse = 0.4;
+    blue_glass_sphere.material.specular = 0.9;
+    blue_glass_sphere.material.shininess = 300.0;
+    blue_glass_sphere.material.reflective = 0.9;
+    blue_glass_sphere.material.transparency = 0.9;
+    blue_glass_sphere.material.refractive_index = 1.5;
+
+    let mut green_glass_sphere = Sphere::default();
+    green_glass_sphere.transform(scale(0.5, 0.5, 0.5).translate(-0.7, 0.5, -0.8));
+    green_glass_sphere.material.pattern = solid_pattern(color(0, 0.2, 0));
+    green_glass_sphere.material.ambient = 0.0;
+    green_glass_sphere.material.diffuse = 0.4;
+    green_glass_sphere.material.specular = 0.9;
+    green_glass_sphere.material.shininess = 300.0;
+    green_glass_sphere.material.reflective = 0.9;
+    green_glass_sphere.material.transparency = 0.9;
+    green_glass_sphere.material.refractive_index = 1.5;
+
+    let mut bg_sphere_1 = Sphere::default();
+    bg_sphere_1.transform(scale(0.4, 0.4, 0.4).translate(4.6, 0.4, 1.0));
+    bg_sphere_1.material.pattern = solid_pattern(color(0.8, 0.5, 0.3));
+    bg_sphere_1.material.shininess = 50.0;
+
+    let mut bg_sphere_2 = Sphere::default();
+    bg_sphere_2.transform(scale(0.3, 0.3, 0.3).translate(4.7, 0.3, 0.4));
+    bg_sphere_2.material.pattern = solid_pattern(color(0.9, 0.4, 0.5));
+    bg_sphere_2.material.shininess = 50.0;
+
+    let mut bg_sphere_3 = Sphere::default();
+    bg_sphere_3.transform(scale(0.5, 0.5, 0.5).translate(-1.0, 0.5, 4.5));
+    bg_sphere_3.material.pattern = solid_pattern(color(0.4, 0.9, 0.6));
+    bg_sphere_3.material.shininess = 50.0;
+
+    let mut bg_sphere_4 = Sphere::default();
+    bg_sphere_4.transform(scale(0.3, 0.3, 0.3).translate(-1.7, 0.3, 4.7));
+    bg_sphere_4.material.pattern = solid_pattern(color(0.4, 0.6, 0.9));
+    bg_sphere_4.material.shininess = 50.0;
+
+    let world = World::new(
+        vec![
+            Box::new(floor),
+            Box::new(ceiling),
+            Box::new(west_wall),
+            Box::new(east_wall),
+            Box::new(north_wall),
+            Box::new(south_wall),
+            Box::new(red_sphere),
+            Box::new(blue_glass_sphere),
+            Box::new(green_glass_sphere),
+            Box::new(bg_sphere_1),
+            Box::new(bg_sphere_2),
+            Box::new(bg_sphere_3),
+            Box::new(bg_sphere_4),
+        ],
+        vec![PointLight::new(point(-4.9, 4.9, -1), color(1, 1, 1))],
+    );
+
+    let camera = Camera::new(
+        width,
+        height,
+        1.152,
+        view_transform(
+            point(-2.6, 1.5, -5.9),
+            point(-0.6, 2.5, -0.8),
+            vector(0, 1, 0),
+        ),
+    );
+
+    (camera, world)
+}
+
+pub fn reflective_spheres(width: usize, height: usize) -> (Camera, World) {
+    let mut floor = Plane::default();
+    let mut floor_material = Material::default();
+    floor_material.pattern = checkers_pattern(WHITE, BLACK, None);
+    floor_material.reflective = 0.4;
+    floor_material.diffuse = 0.7;
+    floor_material.specular = 0.3;
+    floor.material = floor_material;
+
+    let mut middle = Sphere::default();
+    middle.transform(translate(-0.5, 1, 0.5));
+    let mut middle_material = Material::default();
+    middle_material.pattern = solid_pattern(color(0.1, 1, 0.5));
+    middle_material.diffuse = 0.7;
+    middle_material.specular = 0.3;
+    middle_material.reflective = 0.1;
+    middle.material = middle_material;
+
+    let mut right = Sphere::default();
+    right.transform(translate(1.5, 0.5, 1.5) * scale(0.5, 0.5, 0.5));
+    let mut right_material = Material::default();
+    right_material.pattern = solid_pattern(WHITE);
+    right_material.diffuse = 0.7;
+    right_material.specular = 0.2;
+    right_material.reflective = 1.0;
+    right_material.shininess = 1.0;
+    right.material = right_material;
+
+    let mut left = Sphere::default();
+    left.transform(translate(-1.5, 0.33, -0.75) * scale(0.33, 0.33, 0.33));
+    let mut left_material = Material::default();
+    left_material.diffuse = 0.7;
+    left_material.specular = 0.3;
+    left_material.reflective = 0.1;
+    left_material.pattern = gradient_pattern(color(1, 1, 0), color(1, 0, 1), rotate_x(30));
+    left.material = left_material;
+
+    let world = World::new(
+        vec![
+            Box::new(left),
+            Box::new(right),
+            Box::new(middle),
+            Box::new(floor),
+        ],
+        vec![PointLight::new(point(-10, 10, -10), color(1, 1, 1))],
+    );
+
+    let camera = Camera::new(
+        width,
+        height,
+        PI / 3.0,
+        view_transform(point(0, 1.5, -5), point(0, 1, 0), vector(0, 1, 0)),
+    );
+
+    (camera, world)
+}
+
+// a glass sphere over a checkered floor
+pub fn refraction(width: usize, height: usize) -> (Camera, World) {
+    let mut floor = Plane::default();
+    let mut floor_material = Material::default();
+    floor_material.pattern = checkers_pattern(WHITE, BLACK, None);
+    floor_material.reflective = 0.4;
+    floor_material.diffuse = 0.7;
+    floor_material.specular = 0.3;
+    floor.material = floor_material;
+
+    let mut sphere = Sphere::default();
+    sphere.transform(translate(-0.5, 1, 0.5));
+    let mut sphere_material = Material::default();
+    sphere_material.pattern = solid_pattern(color(0.2, 0, 0));
+    sphere_material.diffuse = 0.1;
+    sphere_material.ambient = 0.1;
+    sphere_material.specular = 0.1;
+    sphere_material.reflective = 0.9;
+    sphere_material.refractive_index = 1.5;
+    sphere_material.transparency = 1.0;
+    sphere_material.shininess = 300.0;
+    sphere.material = sphere_material;
+
+    let world = World::new(
+        vec![Box::new(sphere), Box::new(floor)],
+        vec![PointLight::new(point(-10, 10, -10), color(1, 1, 1))],
+    );
+
+    let camera = Camera::new(
+        width,
+        height,
+        PI / 3.0,
+        view_transform(point(0, 1.5, -5), point(0, 1, 0), vector(0, 1, 0)),
+    );
+
+    (camera, world)
+}
+
+// one frame of a camera orbiting a bouncing ball over two seconds. the
+// shutter stays open for `exposure` seconds, blurring the ball.
+pub fn turntable(time: f64, exposure: f64, width: usize, height: usize) -> (Camera, World) {
+    // one full turn of the camera, easing in and out of the orbit
+    let angle = Track::new(0.0)
+        .key(2.0, 2.0 * PI, Easing::EASE_IN_OUT)
+        .unwrap();
+    // the ball drops onto the floor and settles
+    let bounce = Track::new(translate(0, 2, 0))
+        .key(0.6, translate(0, 1, 0), Easing::EASE_IN)
+        .unwrap()
+        .key(1.2, translate(0, 1.5, 0), Easing::EASE_OUT)
+        .unwrap()
+        .key(1.8, translate(0, 1, 0), Easing::EASE_IN)
+        .unwrap();
+    let tint = Track::new(color(1, 0.2, 0.2))
+        .key(2.0, color(0.2, 0.2, 1), Easing::Linear)
+        .unwrap();
+    let light = Track::new(point(-10, 10, -10))
+        .key(2.0, point(10, 10, -10), Easing::Linear)
+        .unwrap();
+
+    let mut floor = Plane::default();
+    floor.material.pattern = checkers_pattern(WHITE, BLACK, None);
+    floor.material.reflective = 0.2;
+
+    let shutter_close = time + exposure;
+    let mut ball = Sphere::default();
+    ball.set_motion(bounce.motion(time, shutter_close).unwrap());
+    ball.material.pattern = solid_pattern(tint.value_at(time));
+    ball.material.specular = 0.6;
+
+    let world = World::new(
+        vec![Box::new(floor), Box::new(ball)],
+        vec![PointLight::new(light.value_at(time), color(1, 1, 1))],
+    );
+
+    let angle = angle.value_at(time);
+    let from = point(-5.0 * angle.sin(), 2.5, -5.0 * angle.cos());
+    let camera = Camera::new(
+        width,
+        height,
+        PI / 3.0,
+        view_transform(from, point(0, 1, 0), vector(0, 1, 0)),
+    )
+    .with_shutter(time, shutter_close)
+    .with_samples(4);
+
+    (camera, world)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::compare::compare;
+    use crate::image::PpmFormat;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // references are 8 bit, so renders are quantised the same way before
+    // comparing. a sample may then be off by one step where it sits on a
+    // rounding boundary, and by another for floating point differences
+    // between platforms that reflections and refractions can magnify
+    const TOLERANCE: f64 = 2.0 / 255.0;
+
+    // set UPDATE_GOLDEN=1 to rewrite the references after a deliberate change
+    fn assert_matches_golden(name: &str, (camera, world): (Camera, World)) {
+        let path = format!("{}/tests/golden/{}.ppm", env!("CARGO_MANIFEST_DIR"), name);
+        let mut ppm = vec![];
+        camera
+            .render(world)
+            .write_ppm(&mut ppm, PpmFormat::Binary)
+            .unwrap();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, &ppm).unwrap();
+        }
+
+        let expected = Canvas::open(&path).unwrap();
+        let actual = Canvas::from_ppm(&ppm).unwrap();
+        let comparison = compare(&expected, &actual).unwrap();
+        assert!(
+            comparison.max_error <= TOLERANCE,
+            "{} differs from its golden image by {}",
+            name,
+            comparison.max_error
+        );
+    }
+
+    #[test]
+    fn default_world_matches_its_golden_image() {
+        let from = point(0, 1.5, -5);
+        let camera = Camera::new(
+            32,
+            24,
+            PI / 3.0,
+            view_transform(from, point(0, 0, 0), vector(0, 1, 0)),
+        );
+        assert_matches_golden("default_world", (camera, World::default()));
+    }
+
+    #[test]
+    fn cubes_match_their_golden_image() {
+        assert_matches_golden("cubes", cubes(40, 20));
+    }
+
+    #[test]
+    fn jenga_matches_its_golden_image() {
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_matches_golden("jenga", jenga(20, 40, &mut rng));
+    }
+
+    // the camera sits just outside the south wall, looking at its unlit
+    // back, so this scene has always rendered black
+    #[test]
+    fn reflect_refract_matches_its_golden_image() {
+        assert_matches_golden("reflect_refract", reflect_refract(40, 30));
+    }
+
+    #[test]
+    fn reflective_spheres_match_their_golden_image() {
+        assert_matches_golden("reflective_spheres", reflective_spheres(40, 20));
+    }
+
+    #[test]
+    fn refraction_matches_its_golden_image() {
+        assert_matches_golden("refraction", refraction(40, 20));
+    }
+
+    #[test]
+    fn turntable_matches_its_golden_image() {
+        assert_matches_golden("turntable", turntable(0.5, 0.5 / 24.0, 32, 18));
+    }
+}
@@ -1,7 +1,9 @@
 use crate::consts::EPSILON;
 use crate::intersection::{intersection, Intersection};
+use crate::mapping::{face_from_point, CubeFace, Mapping};
 use crate::material::Material;
 use crate::matrix::Matrix;
+use crate::motion::Motion;
 use crate::object::Object;
 use crate::ray::Ray;
 use crate::tuple::{vector, Tuple};
@@ -37,6 +39,7 @@ pub struct Cube {
     transform: Matrix,
     inverse: Matrix,
     id: Uuid,
+    motion: Option<Motion>,
 }
 
 impl Cube {
@@ -46,6 +49,7 @@ impl Cube {
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
             id: Uuid::new_v4(),
+            motion: None,
         }
     }
 
@@ -54,7 +58,8 @@ impl Cube {
             material,
             transform,
             inverse: transform.inverse().unwrap(),
-            id: Uuid::new_v4()
+            id: Uuid::new_v4(),
+            motion: None,
         }
     }
 
@@ -85,6 +90,10 @@ impl Object for Cube {
         self.id
     }
 
+    fn uv_mapping(&self) -> Mapping {
+        Mapping::Cubic
+    }
+
     fn normal_at(&self, p: Tuple) -> Tuple {
         let object_point = self.inverse * p;
 
@@ -107,6 +116,16 @@ impl Object for Cube {
         world_normal.normalize()
     }
 
+    fn tangent_at(&self, p: Tuple) -> Tuple {
+        let object_tangent = match face_from_point(self.inverse * p) {
+            CubeFace::Front | CubeFace::Up | CubeFace::Down => vector(1, 0, 0),
+            CubeFace::Back => vector(-1, 0, 0),
+            CubeFace::Left => vector(0, 0, 1),
+            CubeFace::Right => vector(0, 0, -1),
+        };
+        (self.transform * object_tangent).normalize()
+    }
+
     fn transformation(&self) -> Matrix {
         self.transform
     }
@@ -116,8 +135,23 @@ impl Object for Cube {
     }
 
     fn transform(&mut self, matrix: Matrix) {
-        self.transform = matrix * self.transform;
+        match self.motion.take() {
+            Some(motion) => self.set_motion(motion.transformed(&matrix).unwrap()),
+            None => {
+                self.transform = matrix * self.transform;
+                self.inverse = self.transform.inverse().unwrap();
+            }
+        }
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    fn set_motion(&mut self, motion: Motion) {
+        self.transform = motion.transform_at(0.0);
         self.inverse = self.transform.inverse().unwrap();
+        self.motion = Some(motion);
     }
 
     fn inverse(&self) -> Matrix {
@@ -133,7 +167,7 @@ impl Object for Cube {
     }
 
     fn intersect(&self, ray: Ray) -> Result<Vec<Intersection>, ()> {
-        let ray2 = ray.transform(self.inverse);
+        let ray2 = ray.transform(self.inverse_at(ray.time));
 
         let (x_t_min, x_t_max) = Self::check_axis(ray2.origin.x, ray2.direction.x);
         let (y_t_min, y_t_max) = Self::check_axis(ray2.origin.y, ray2.direction.y);
@@ -216,4 +250,21 @@ mod tests {
             assert!(cube.normal_at(*p) == *n);
         }
     }
+
+    #[test]
+    fn tangent_of_cube_depends_on_the_face() {
+        let expected = [
+            (point(0, 0, 1), vector(1, 0, 0)),
+            (point(0, 0, -1), vector(-1, 0, 0)),
+            (point(-1, 0, 0), vector(0, 0, 1)),
+            (point(1, 0, 0), vector(0, 0, -1)),
+            (point(0, 1, 0), vector(1, 0, 0)),
+            (point(0, -1, 0), vector(1, 0, 0)),
+        ];
+
+        for (p, t) in expected.iter() {
+            let cube = Cube::default();
+            assert!(cube.tangent_at(*p) == *t);
+        }
+    }
 }
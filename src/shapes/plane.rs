@@ -1,7 +1,9 @@
 use crate::consts::EPSILON;
 use crate::intersection::{intersection, Intersection};
+use crate::mapping::Mapping;
 use crate::material::Material;
 use crate::matrix::Matrix;
+use crate::motion::Motion;
 use crate::object::Object;
 use crate::ray::Ray;
 use crate::tuple::{vector, Tuple};
@@ -13,6 +15,7 @@ pub struct Plane {
     pub transform: Matrix,
     inverse: Matrix,
     id: Uuid,
+    motion: Option<Motion>,
 }
 
 impl Plane {
@@ -22,6 +25,7 @@ impl Plane {
             transform: Matrix::identity(),
             inverse: Matrix::identity(),
             id: Uuid::new_v4(),
+            motion: None,
         }
     }
 
@@ -31,6 +35,7 @@ impl Plane {
             transform,
             inverse: transform.inverse().unwrap(),
             id: Uuid::new_v4(),
+            motion: None,
         }
     }
 }
@@ -40,10 +45,18 @@ impl Object for Plane {
         self.id
     }
 
+    fn uv_mapping(&self) -> Mapping {
+        Mapping::Planar
+    }
+
     fn normal_at(&self, _p: Tuple) -> Tuple {
         vector(0, 1, 0)
     }
 
+    fn tangent_at(&self, _p: Tuple) -> Tuple {
+        (self.transform * vector(1, 0, 0)).normalize()
+    }
+
     fn transformation(&self) -> Matrix {
         self.transform
     }
@@ -53,8 +66,23 @@ impl Object for Plane {
     }
 
     fn transform(&mut self, matrix: Matrix) {
-        self.transform = matrix * self.transform;
+        match self.motion.take() {
+            Some(motion) => self.set_motion(motion.transformed(&matrix).unwrap()),
+            None => {
+                self.transform = matrix * self.transform;
+                self.inverse = self.transform.inverse().unwrap();
+            }
+        }
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    fn set_motion(&mut self, motion: Motion) {
+        self.transform = motion.transform_at(0.0);
         self.inverse = self.transform.inverse().unwrap();
+        self.motion = Some(motion);
     }
 
     fn inverse(&self) -> Matrix {
@@ -70,7 +98,7 @@ impl Object for Plane {
     }
 
     fn intersect(&self, ray: Ray) -> Result<Vec<Intersection>, ()> {
-        let ray2 = ray.transform(self.inverse);
+        let ray2 = ray.transform(self.inverse_at(ray.time));
         if f64::abs(ray2.direction.y) < EPSILON {
             return Ok(vec![]);
         }
@@ -83,7 +111,9 @@ impl Object for Plane {
 mod tests {
     use super::*;
     use crate::ray::ray;
+    use crate::transformations::rotate_y;
     use crate::tuple::point;
+    use std::f64::consts::PI;
 
     #[test]
     fn normal_of_plane_is_constant() {
@@ -126,4 +156,13 @@ mod tests {
         assert!(xs.len() == 1);
         assert!(xs[0].t == 1.0)
     }
+
+    #[test]
+    fn tangent_of_plane_follows_the_x_axis() {
+        let mut p = Plane::default();
+        assert!(p.tangent_at(point(3, 0, 4)) == vector(1, 0, 0));
+
+        p.transform(rotate_y(PI / 2.0));
+        assert!(p.tangent_at(point(3, 0, 4)) == vector(0, 0, -1));
+    }
 }
@@ -1,6 +1,8 @@
 use crate::intersection::*;
+use crate::mapping::Mapping;
 use crate::material::Material;
 use crate::matrix::*;
+use crate::motion::Motion;
 use crate::object::Object;
 use crate::ray::*;
 use crate::tuple::*;
@@ -12,6 +14,7 @@ pub struct Sphere {
     pub material: Material,
     inverse: Matrix,
     id: Uuid,
+    motion: Option<Motion>,
 }
 
 impl Sphere {
@@ -21,6 +24,7 @@ impl Sphere {
             inverse: Matrix::identity(),
             material: Material::default(),
             id: Uuid::new_v4(),
+            motion: None,
         }
     }
 }
@@ -30,6 +34,10 @@ impl Object for Sphere {
         self.id
     }
 
+    fn uv_mapping(&self) -> Mapping {
+        Mapping::Spherical
+    }
+
     fn material(&self) -> &Material {
         &self.material
     }
@@ -37,6 +45,16 @@ impl Object for Sphere {
         &mut self.material
     }
 
+    fn tangent_at(&self, p: Tuple) -> Tuple {
+        let object_point = self.inverse * p;
+        let object_tangent = match object_point.x == 0.0 && object_point.z == 0.0 {
+            // the poles have no meaningful u direction
+            true => vector(1, 0, 0),
+            false => vector(-object_point.z, 0, object_point.x),
+        };
+        (self.transform * object_tangent).normalize()
+    }
+
     fn transformation(&self) -> Matrix {
         self.transform
     }
@@ -46,8 +64,23 @@ impl Object for Sphere {
     }
 
     fn transform(&mut self, matrix: Matrix) {
-        self.transform = matrix * self.transform;
+        match self.motion.take() {
+            Some(motion) => self.set_motion(motion.transformed(&matrix).unwrap()),
+            None => {
+                self.transform = matrix * self.transform;
+                self.inverse = self.transform.inverse().unwrap();
+            }
+        }
+    }
+
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    fn set_motion(&mut self, motion: Motion) {
+        self.transform = motion.transform_at(0.0);
         self.inverse = self.transform.inverse().unwrap();
+        self.motion = Some(motion);
     }
 
     fn inverse(&self) -> Matrix {
@@ -58,7 +91,7 @@ impl Object for Sphere {
         // the vector from the sphere's center, to the ray origin
         // remember: the sphere is centered at the world origin
 
-        let ray2 = ray.transform(self.inverse);
+        let ray2 = ray.transform(self.inverse_at(ray.time));
         let sphere_to_ray = ray2.origin - point(0.0, 0.0, 0.0);
 
         let a = ray2.direction.dot(ray2.direction);
@@ -95,6 +128,7 @@ pub fn sphere(transform: Matrix, material: Material) -> Sphere {
         transform,
         material,
         id: Uuid::new_v4(),
+        motion: None,
         inverse: transform.inverse().unwrap(),
     }
 }
@@ -111,6 +145,7 @@ pub fn glass_sphere() -> Sphere {
 mod tests {
 
     use super::*;
+    use crate::motion::Motion;
     use crate::transformations::*;
     use std::f64::consts::PI;
 
@@ -310,4 +345,59 @@ mod tests {
         assert!(s.material.refractive_index == 1.5);
         assert!(s.material.transparency == 1.0);
     }
+
+    #[test]
+    fn tangent_on_sphere_follows_increasing_u() {
+        let s = Sphere::default();
+
+        assert!(s.tangent_at(point(0, 0, -1)) == vector(1, 0, 0));
+        assert!(s.tangent_at(point(1, 0, 0)) == vector(0, 0, 1));
+        assert!(s.tangent_at(point(0, 1, 0)) == vector(1, 0, 0));
+    }
+
+    #[test]
+    fn a_moving_sphere_starts_in_its_first_pose() {
+        let mut s = Sphere::default();
+        s.set_motion(Motion::new(translate(0, 1, 0), translate(4, 1, 0)).unwrap());
+
+        assert!(s.transformation() == translate(0, 1, 0));
+        assert!(s.transformation_at(0.5) == translate(2, 1, 0));
+        assert!(s.inverse_at(1.0) == translate(-4, -1, 0));
+        assert!(s.rest_pose_at(0.5) == translate(-2, 0, 0));
+        assert!(Sphere::default().rest_pose_at(0.5) == identity());
+    }
+
+    #[test]
+    fn intersecting_a_moving_sphere_at_different_times() {
+        let mut s = Sphere::default();
+        s.set_motion(Motion::new(identity(), translate(4, 0, 0)).unwrap());
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+
+        assert!(s.intersect(r).unwrap().len() == 2);
+        assert!(s.intersect(r.with_time(1.0)).unwrap().is_empty());
+        let xs = s
+            .intersect(ray(point(4, 0, -5), vector(0, 0, 1)).with_time(1.0))
+            .unwrap();
+        assert!(xs[0].t == 4.0);
+    }
+
+    #[test]
+    fn a_motion_replaces_the_earlier_transform() {
+        let mut s = Sphere::default();
+        s.transform(scale(2, 2, 2));
+        s.set_motion(Motion::new(identity(), translate(4, 0, 0)).unwrap());
+
+        assert!(s.transformation() == identity());
+    }
+
+    #[test]
+    fn transforming_a_moving_sphere_transforms_every_pose() {
+        let mut s = Sphere::default();
+        s.set_motion(Motion::new(identity(), translate(4, 0, 0)).unwrap());
+        s.transform(scale(2, 2, 2));
+
+        assert!(s.transformation() == scale(2, 2, 2));
+        assert!(s.transformation_at(0.5) == translate(2, 0, 0).scale(2.0, 2.0, 2.0));
+        assert!(s.inverse_at(1.0) == scale(0.5, 0.5, 0.5).translate(-4.0, 0.0, 0.0));
+    }
 }
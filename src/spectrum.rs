@@ -0,0 +1,171 @@
+use crate::color::{color, Color, BLACK};
+
+pub const MIN_WAVELENGTH: f64 = 380.0;
+pub const MAX_WAVELENGTH: f64 = 780.0;
+
+// wavelength-dependent refractive index. the coefficients are fitted to
+// wavelengths in micrometres, though `refractive_index` takes nanometres.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dispersion {
+    None,
+    // n = a + b / λ²
+    Cauchy(f64, f64),
+    // n² = 1 + Σ bᵢλ² / (λ² - cᵢ)
+    Sellmeier([f64; 3], [f64; 3]),
+}
+
+impl Dispersion {
+    pub fn refractive_index(&self, wavelength: f64, default: f64) -> f64 {
+        // nm to µm
+        let l2 = (wavelength / 1000.0).powi(2);
+        match self {
+            Dispersion::None => default,
+            Dispersion::Cauchy(a, b) => a + b / l2,
+            Dispersion::Sellmeier(b, c) => {
+                let sum = (0..3).fold(0.0, |acc, i| acc + b[i] * l2 / (l2 - c[i]));
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+pub fn bk7_glass() -> Dispersion {
+    Dispersion::Sellmeier(
+        [1.039_612_12, 0.231_792_344, 1.010_469_45],
+        [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+    )
+}
+
+// piecewise gaussian used by the analytic fit of the CIE 1931 observer
+// (Wyman, Sloan & Shirley 2013)
+fn lobe(wavelength: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+    let sigma = if wavelength < mu {
+        sigma_low
+    } else {
+        sigma_high
+    };
+    let t = (wavelength - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+pub fn cie_xyz(wavelength: f64) -> (f64, f64, f64) {
+    let l = wavelength;
+    let x = 1.056 * lobe(l, 599.8, 37.9, 31.0) + 0.362 * lobe(l, 442.0, 16.0, 26.7)
+        - 0.065 * lobe(l, 501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(l, 568.8, 46.9, 40.5) + 0.286 * lobe(l, 530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(l, 437.0, 11.8, 36.0) + 0.681 * lobe(l, 459.0, 26.0, 13.8);
+    (x, y, z)
+}
+
+// linear sRGB response to a single wavelength, with out-of-gamut negatives clipped
+pub fn wavelength_to_rgb(wavelength: f64) -> Color {
+    let (x, y, z) = cie_xyz(wavelength);
+    let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+    let g = -0.969_266_0 * x + 1.876_010_8 * y + 0.041_556_0 * z;
+    let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+    color(r.max(0.0), g.max(0.0), b.max(0.0))
+}
+
+// the fewest wavelengths that give every channel some response
+pub const MIN_SPECTRAL_SAMPLES: usize = 3;
+
+// evenly spaced wavelengths across the visible range, each paired with the weight
+// its radiance contributes to a pixel. The weights sum to white, so a scene
+// without dispersion renders the same as it does in RGB. With fewer than
+// MIN_SPECTRAL_SAMPLES a channel may get no response at all, and its weights
+// are left at zero.
+pub fn spectral_samples(count: usize) -> Vec<(f64, Color)> {
+    let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / count as f64;
+    let samples: Vec<(f64, Color)> = (0..count)
+        .map(|i| {
+            let wavelength = MIN_WAVELENGTH + (i as f64 + 0.5) * step;
+            (wavelength, wavelength_to_rgb(wavelength))
+        })
+        .collect();
+
+    let total = samples.iter().fold(BLACK, |acc, (_, w)| acc + *w);
+    let share = |w: f64, total: f64| if total > 0.0 { w / total } else { 0.0 };
+    samples
+        .into_iter()
+        .map(|(wavelength, w)| {
+            let weight = color(
+                share(w.0, total.0),
+                share(w.1, total.1),
+                share(w.2, total.2),
+            );
+            (wavelength, weight)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WHITE;
+    use crate::consts::EPSILON;
+
+    #[test]
+    fn no_dispersion_uses_the_scalar_index() {
+        assert!(Dispersion::None.refractive_index(450.0, 1.5) == 1.5);
+    }
+
+    #[test]
+    fn cauchy_index_falls_with_wavelength() {
+        let d = Dispersion::Cauchy(1.5046, 0.0042);
+
+        assert!(f64::abs(d.refractive_index(500.0, 1.0) - 1.5214) < EPSILON);
+        assert!(d.refractive_index(400.0, 1.0) > d.refractive_index(700.0, 1.0));
+    }
+
+    #[test]
+    fn sellmeier_index_for_bk7_glass() {
+        let d = bk7_glass();
+
+        assert!(f64::abs(d.refractive_index(587.6, 1.0) - 1.5168) < EPSILON);
+        assert!(d.refractive_index(400.0, 1.0) > d.refractive_index(700.0, 1.0));
+    }
+
+    #[test]
+    fn wavelengths_map_to_their_hue() {
+        let blue = wavelength_to_rgb(450.0);
+        let green = wavelength_to_rgb(530.0);
+        let red = wavelength_to_rgb(650.0);
+
+        assert!(blue.2 > blue.0 && blue.2 > blue.1);
+        assert!(green.1 > green.0 && green.1 > green.2);
+        assert!(red.0 > red.1 && red.0 > red.2);
+    }
+
+    #[test]
+    fn spectral_sample_weights_sum_to_white() {
+        let samples = spectral_samples(16);
+        let total = samples.iter().fold(BLACK, |acc, (_, w)| acc + *w);
+
+        assert!(samples.len() == 16);
+        assert!(total == WHITE);
+        assert!(samples[0].0 > MIN_WAVELENGTH);
+        assert!(samples[15].0 < MAX_WAVELENGTH);
+    }
+
+    #[test]
+    fn no_spectral_samples() {
+        assert!(spectral_samples(0).is_empty());
+    }
+
+    #[test]
+    fn a_single_spectral_sample_has_no_blue_weight() {
+        let samples = spectral_samples(1);
+
+        assert!(samples.len() == 1);
+        assert!(samples[0].0 == 580.0);
+        assert!(samples[0].1 == color(1, 1, 0));
+    }
+
+    #[test]
+    fn the_fewest_spectral_samples_cover_every_channel() {
+        let samples = spectral_samples(MIN_SPECTRAL_SAMPLES);
+        let total = samples.iter().fold(BLACK, |acc, (_, w)| acc + *w);
+
+        assert!(total == WHITE);
+    }
+}
@@ -0,0 +1,311 @@
+use super::camera::Camera;
+use super::canvas::Canvas;
+use super::color::Color;
+use super::lens::Lens;
+use super::transformations::view_transform;
+use super::tuple::Tuple;
+use super::world::World;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StereoLayout {
+    // left eye on the left
+    SideBySide,
+    // left eye on top
+    TopBottom,
+    // red from the left eye, green and blue from the right
+    Anaglyph,
+}
+
+// two cameras with parallel axes either side of a viewpoint. each eye's view
+// plane is shifted so that objects at the convergence distance line up in
+// both images, which keeps them at screen depth. the eyes always use a
+// perspective projection: panoramic stereo needs a separate eye position
+// for every direction, which a pair of cameras can't give.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StereoRig {
+    pub h_size: usize,
+    pub v_size: usize,
+    pub field_of_view: f64,
+    pub from: Tuple,
+    pub to: Tuple,
+    pub up: Tuple,
+    pub interocular: f64,
+    pub convergence: f64,
+    // passed on to both eyes, as for a single `Camera`
+    pub lens: Option<Lens>,
+    pub samples: usize,
+    pub spectral_samples: usize,
+    pub shutter: (f64, f64),
+}
+
+impl StereoRig {
+    // starts with a 65mm (in metres) eye separation, converging on `to`
+    pub fn new<T: Into<f64>>(
+        h_size: usize,
+        v_size: usize,
+        field_of_view: T,
+        from: Tuple,
+        to: Tuple,
+        up: Tuple,
+    ) -> StereoRig {
+        StereoRig {
+            h_size,
+            v_size,
+            field_of_view: field_of_view.into(),
+            from,
+            to,
+            up,
+            interocular: 0.065,
+            convergence: (to - from).magnitude(),
+            lens: None,
+            samples: 1,
+            spectral_samples: 0,
+            shutter: (0.0, 0.0),
+        }
+    }
+
+    pub fn with_interocular(self, interocular: f64) -> StereoRig {
+        StereoRig {
+            interocular,
+            ..self
+        }
+    }
+
+    pub fn with_convergence(self, convergence: f64) -> StereoRig {
+        StereoRig {
+            convergence,
+            ..self
+        }
+    }
+
+    pub fn with_lens(self, lens: Lens) -> StereoRig {
+        StereoRig {
+            lens: Some(lens),
+            ..self
+        }
+    }
+
+    pub fn with_samples(self, samples: usize) -> StereoRig {
+        StereoRig {
+            samples: samples.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_spectral_samples(self, samples: usize) -> StereoRig {
+        StereoRig {
+            spectral_samples: samples,
+            ..self
+        }
+    }
+
+    pub fn with_shutter(self, open: f64, close: f64) -> StereoRig {
+        StereoRig {
+            shutter: (open, close),
+            ..self
+        }
+    }
+
+    // the left and right eye cameras
+    pub fn cameras(&self) -> (Camera, Camera) {
+        let forward = (self.to - self.from).normalize();
+        let left = forward.cross(&self.up.normalize()).normalize();
+        let half = self.interocular / 2.0;
+        // how far the centre of the frame is from each eye's axis at the
+        // convergence distance, on a view plane one unit away
+        let shift = half / self.convergence;
+
+        let eye = |offset: f64, shift: f64| {
+            let from = self.from + left * offset;
+            let to = self.to + left * offset;
+            let transform = view_transform(from, to, self.up);
+            let camera = Camera::new(self.h_size, self.v_size, self.field_of_view, transform)
+                .with_shift(shift, 0.0)
+                .with_samples(self.samples)
+                .with_spectral_samples(self.spectral_samples)
+                .with_shutter(self.shutter.0, self.shutter.1);
+            match self.lens {
+                Some(lens) => camera.with_lens(lens),
+                None => camera,
+            }
+        };
+
+        (eye(half, shift), eye(-half, -shift))
+    }
+
+    pub fn render(&self, world: &World, layout: StereoLayout) -> Canvas {
+        let (left, right) = self.cameras();
+        let left = left.render_passes(world).beauty;
+        let right = right.render_passes(world).beauty;
+        combine(&left, &right, layout)
+    }
+}
+
+// packs a pair of eye images, which must be the same size, into one frame
+pub fn combine(left: &Canvas, right: &Canvas, layout: StereoLayout) -> Canvas {
+    assert!(
+        left.width == right.width && left.height == right.height,
+        "eye images have different sizes"
+    );
+    let (width, height) = (left.width, left.height);
+
+    match layout {
+        StereoLayout::SideBySide => {
+            let mut image = Canvas::new(width * 2, height);
+            for y in 0..height {
+                for x in 0..width {
+                    image.write_pixel(x, y, *left.get_pixel(x, y));
+                    image.write_pixel(x + width, y, *right.get_pixel(x, y));
+                }
+            }
+            image
+        }
+        StereoLayout::TopBottom => {
+            let mut image = Canvas::new(width, height * 2);
+            for y in 0..height {
+                for x in 0..width {
+                    image.write_pixel(x, y, *left.get_pixel(x, y));
+                    image.write_pixel(x, y + height, *right.get_pixel(x, y));
+                }
+            }
+            image
+        }
+        StereoLayout::Anaglyph => {
+            let mut image = Canvas::new(width, height);
+            for (i, (l, r)) in left.pixels.iter().zip(right.pixels.iter()).enumerate() {
+                image[i] = Color(l.0, r.1, r.2);
+            }
+            image
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+    use crate::lens::ApertureShape;
+    use crate::tuple::{point, vector};
+    use std::f64::consts::PI;
+
+    fn rig() -> StereoRig {
+        StereoRig::new(
+            101,
+            101,
+            PI / 2.0,
+            point(0, 0, -5),
+            point(0, 0, 0),
+            vector(0, 1, 0),
+        )
+    }
+
+    #[test]
+    fn rig_converges_on_its_target_by_default() {
+        let rig = rig();
+
+        assert!(rig.interocular == 0.065);
+        assert!(rig.convergence == 5.0);
+    }
+
+    #[test]
+    fn eyes_sit_either_side_of_the_viewpoint() {
+        let (left, right) = rig().with_interocular(1.0).cameras();
+
+        let l = left.ray_for_pixel(50, 50);
+        let r = right.ray_for_pixel(50, 50);
+        // looking down +z, the camera's left is -x
+        assert!(l.origin == point(-0.5, 0, -5));
+        assert!(r.origin == point(0.5, 0, -5));
+    }
+
+    #[test]
+    fn eyes_agree_at_the_convergence_distance() {
+        let (left, right) = rig().with_interocular(1.0).with_convergence(4.0).cameras();
+
+        let l = left.ray_for_pixel(50, 50);
+        let r = right.ray_for_pixel(50, 50);
+        let l_distance = (point(0, 0, -1) - l.origin).magnitude();
+        let r_distance = (point(0, 0, -1) - r.origin).magnitude();
+        assert!(l.position(l_distance) == point(0, 0, -1));
+        assert!(r.position(r_distance) == point(0, 0, -1));
+    }
+
+    #[test]
+    fn eyes_share_the_rigs_lens_samples_and_shutter() {
+        let lens = Lens::new(0.2, 5.0, ApertureShape::Circle);
+        let rig = StereoRig::new(
+            11,
+            11,
+            PI / 2.0,
+            point(0, 0, -5),
+            point(0, 0, 0),
+            vector(0, 1, 0),
+        )
+        .with_interocular(1.0)
+        .with_lens(lens)
+        .with_samples(4)
+        .with_shutter(0.0, 1.0);
+        let (left, _) = rig.cameras();
+
+        let transform = view_transform(point(-0.5, 0, -5), point(-0.5, 0, 0), vector(0, 1, 0));
+        let expected = Camera::new(11, 11, PI / 2.0, transform)
+            .with_shift(0.1, 0.0)
+            .with_lens(lens)
+            .with_samples(4)
+            .with_shutter(0.0, 1.0);
+        let pinhole = Camera::new(11, 11, PI / 2.0, transform).with_shift(0.1, 0.0);
+
+        let image = left.render(World::default());
+        assert!(image == expected.render(World::default()));
+        assert!(image != pinhole.render(World::default()));
+    }
+
+    #[test]
+    fn combining_eyes_side_by_side_and_top_to_bottom() {
+        let mut left = Canvas::new(2, 1);
+        let mut right = Canvas::new(2, 1);
+        left.write_pixel(1, 0, color(1, 0, 0));
+        right.write_pixel(0, 0, color(0, 0, 1));
+
+        let sbs = combine(&left, &right, StereoLayout::SideBySide);
+        assert!(sbs.width == 4 && sbs.height == 1);
+        assert!(sbs.get_pixel(1, 0) == &color(1, 0, 0));
+        assert!(sbs.get_pixel(2, 0) == &color(0, 0, 1));
+
+        let tb = combine(&left, &right, StereoLayout::TopBottom);
+        assert!(tb.width == 2 && tb.height == 2);
+        assert!(tb.get_pixel(1, 0) == &color(1, 0, 0));
+        assert!(tb.get_pixel(0, 1) == &color(0, 0, 1));
+    }
+
+    #[test]
+    fn anaglyph_takes_red_from_the_left_eye() {
+        let mut left = Canvas::new(1, 1);
+        let mut right = Canvas::new(1, 1);
+        left.write_pixel(0, 0, color(0.2, 0.4, 0.6));
+        right.write_pixel(0, 0, color(0.7, 0.8, 0.9));
+
+        let anaglyph = combine(&left, &right, StereoLayout::Anaglyph);
+
+        assert!(anaglyph.get_pixel(0, 0) == &color(0.2, 0.8, 0.9));
+    }
+
+    #[test]
+    fn rendering_a_stereo_pair() {
+        let rig = StereoRig::new(
+            11,
+            11,
+            PI / 2.0,
+            point(0, 0, -5),
+            point(0, 0, 0),
+            vector(0, 1, 0),
+        );
+
+        let image = rig.render(&World::default(), StereoLayout::SideBySide);
+
+        assert!(image.width == 22);
+        // both eyes look at the middle of the sphere, from slightly different angles
+        let (l, r) = (image.get_pixel(5, 5), image.get_pixel(16, 5));
+        assert!((l.0 - r.0).abs() < 0.01 && (l.1 - r.1).abs() < 0.01);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::canvas::Canvas;
+use crate::color::{Color, BLACK};
+use crate::image::ImageError;
+use std::path::Path;
+use std::rc::Rc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+// what happens to (u, v) coordinates that fall outside the image
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    Clamp,
+}
+
+// an image sampled at (u, v) coordinates, with (0, 0) at the bottom left
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    image: Rc<Canvas>,
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+impl Texture {
+    pub fn new(image: Canvas, filter: Filter, wrap: Wrap) -> Texture {
+        Texture {
+            image: Rc::new(image),
+            filter,
+            wrap,
+        }
+    }
+
+    // loads any image `Canvas::open` can read
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        filter: Filter,
+        wrap: Wrap,
+    ) -> Result<Texture, ImageError> {
+        Ok(Texture::new(Canvas::open(path)?, filter, wrap))
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    pub fn color_at(&self, u: f64, v: f64) -> Color {
+        let x = u * self.image.width as f64;
+        let y = (1.0 - v) * self.image.height as f64;
+
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // sample positions are at the pixel centres
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let fx = x - x0;
+                let fy = y - y0;
+                // huge coordinates saturate, so the neighbours must too
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let (x1, y1) = (x0.saturating_add(1), y0.saturating_add(1));
+
+                let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x1, y0) * fx;
+                let bottom = self.texel(x0, y1) * (1.0 - fx) + self.texel(x1, y1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let width = self.image.width as i64;
+        let height = self.image.height as i64;
+        // an empty image has nothing to wrap or clamp to
+        if width == 0 || height == 0 {
+            return BLACK;
+        }
+        let (x, y) = match self.wrap {
+            Wrap::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+            Wrap::Clamp => (x.max(0).min(width - 1), y.max(0).min(height - 1)),
+        };
+        *self.image.get_pixel(x as usize, y as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{color, WHITE};
+    use crate::image::PpmFormat;
+
+    // 2x2 image: white, black on the top row and black, grey below
+    fn image() -> Canvas {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, WHITE);
+        canvas.write_pixel(1, 0, BLACK);
+        canvas.write_pixel(0, 1, BLACK);
+        canvas.write_pixel(1, 1, color(0.5, 0.5, 0.5));
+        canvas
+    }
+
+    #[test]
+    fn nearest_filtering_picks_the_containing_pixel() {
+        let texture = Texture::new(image(), Filter::Nearest, Wrap::Repeat);
+
+        assert!(texture.color_at(0.1, 0.9) == WHITE);
+        assert!(texture.color_at(0.9, 0.9) == BLACK);
+        assert!(texture.color_at(0.9, 0.1) == color(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn bilinear_filtering_blends_neighbouring_pixels() {
+        let texture = Texture::new(image(), Filter::Bilinear, Wrap::Clamp);
+
+        assert!(texture.color_at(0.25, 0.75) == WHITE);
+        assert!(texture.color_at(0.5, 0.75) == color(0.5, 0.5, 0.5));
+        assert!(texture.color_at(0.5, 0.5) == color(0.375, 0.375, 0.375));
+    }
+
+    #[test]
+    fn repeat_wraps_coordinates_around() {
+        let texture = Texture::new(image(), Filter::Nearest, Wrap::Repeat);
+
+        assert!(texture.color_at(1.1, 1.9) == WHITE);
+        assert!(texture.color_at(-0.1, 0.9) == BLACK);
+    }
+
+    #[test]
+    fn clamp_holds_the_edge_pixel() {
+        let texture = Texture::new(image(), Filter::Nearest, Wrap::Clamp);
+
+        assert!(texture.color_at(-0.5, 1.5) == WHITE);
+        assert!(texture.color_at(1.5, 1.5) == BLACK);
+    }
+
+    #[test]
+    fn bilinear_filtering_survives_huge_coordinates() {
+        let texture = Texture::new(image(), Filter::Bilinear, Wrap::Clamp);
+
+        assert!(texture.color_at(1e300, -1e300) == color(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn empty_textures_are_black() {
+        let texture = Texture::new(Canvas::new(0, 0), Filter::Bilinear, Wrap::Repeat);
+
+        assert!(texture.color_at(0.5, 0.5) == BLACK);
+    }
+
+    #[test]
+    fn textures_can_be_loaded_from_image_files() {
+        let path = std::env::temp_dir().join(format!("texture-{}.ppm", uuid::Uuid::new_v4()));
+        let mut ppm = vec![];
+        image().write_ppm(&mut ppm, PpmFormat::Binary).unwrap();
+        std::fs::write(&path, ppm).unwrap();
+
+        let texture = Texture::open(&path, Filter::Nearest, Wrap::Repeat).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(texture.color_at(0.1, 0.9) == WHITE);
+        assert!(texture.color_at(0.9, 0.1) == color(128.0 / 255.0, 128.0 / 255.0, 128.0 / 255.0));
+        assert!(Texture::open("no/such/texture.ppm", Filter::Nearest, Wrap::Repeat).is_err());
+    }
+}
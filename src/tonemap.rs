@@ -0,0 +1,123 @@
+use super::canvas::Canvas;
+use super::color::Color;
+use super::image::Gamma;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ToneMapOperator {
+    Clamp,
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+impl ToneMapOperator {
+    pub fn map(&self, v: f64) -> f64 {
+        let v = v.max(0.0);
+        match self {
+            ToneMapOperator::Clamp => v.min(1.0),
+            ToneMapOperator::Reinhard => v / (1.0 + v),
+            ToneMapOperator::Aces => {
+                let mapped = (v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14);
+                mapped.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+// turns a linear render into display values: exposure (in stops), then the
+// operator, then the transfer function
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ToneMap {
+    pub exposure: f64,
+    pub operator: ToneMapOperator,
+    pub gamma: Gamma,
+}
+
+impl Default for ToneMap {
+    fn default() -> ToneMap {
+        ToneMap {
+            exposure: 0.0,
+            operator: ToneMapOperator::Clamp,
+            gamma: Gamma::Srgb,
+        }
+    }
+}
+
+impl ToneMap {
+    pub fn new(exposure: f64, operator: ToneMapOperator, gamma: Gamma) -> ToneMap {
+        ToneMap {
+            exposure,
+            operator,
+            gamma,
+        }
+    }
+
+    pub fn map_color(&self, color: Color) -> Color {
+        let scale = 2_f64.powf(self.exposure);
+        let channel = |v: f64| self.gamma.encode(self.operator.map(v * scale));
+        Color(channel(color.0), channel(color.1), channel(color.2))
+    }
+
+    pub fn apply(&self, canvas: &Canvas) -> Canvas {
+        let mut mapped = canvas.clone();
+        for pixel in mapped.pixels.iter_mut() {
+            *pixel = self.map_color(*pixel);
+        }
+        mapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color;
+
+    #[test]
+    fn clamp_cuts_off_highlights_and_negatives() {
+        let op = ToneMapOperator::Clamp;
+        assert!(op.map(2.5) == 1.0);
+        assert!(op.map(-0.5) == 0.0);
+        assert!(op.map(0.25) == 0.25);
+    }
+
+    #[test]
+    fn reinhard_compresses_towards_one() {
+        let op = ToneMapOperator::Reinhard;
+        assert!(op.map(1.0) == 0.5);
+        assert!(op.map(3.0) == 0.75);
+        assert!(op.map(1000.0) < 1.0);
+    }
+
+    #[test]
+    fn aces_is_black_at_zero_and_saturates() {
+        let op = ToneMapOperator::Aces;
+        assert!(op.map(0.0) == 0.0);
+        assert!((op.map(0.18) - 0.2669).abs() < 0.0001);
+        assert!(op.map(100.0) == 1.0);
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let tonemap = ToneMap::new(1.0, ToneMapOperator::Clamp, Gamma::Linear);
+
+        assert!(tonemap.map_color(color(0.25, 0.1, 0.6)) == color(0.5, 0.2, 1));
+    }
+
+    #[test]
+    fn default_tone_map_applies_the_srgb_curve() {
+        let tonemap = ToneMap::default();
+
+        assert!(tonemap.map_color(color(0.5, 0, 1.5)) == color(0.73536, 0, 1));
+    }
+
+    #[test]
+    fn applying_a_tone_map_leaves_the_render_untouched() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, color(4, 1, 0.5));
+
+        let mapped = ToneMap::new(0.0, ToneMapOperator::Reinhard, Gamma::Linear).apply(&canvas);
+
+        assert!(mapped.get_pixel(0, 0) == &color(0.8, 0.5, 0.33333));
+        assert!(canvas.get_pixel(0, 0) == &color(4, 1, 0.5));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::color::{color, Color};
+use crate::color::{color, Color, BLACK, WHITE};
 use crate::intersection::Hit;
 use crate::intersection::{ComputedIntersection, Intersection};
 use crate::light::PointLight;
@@ -8,7 +8,8 @@ use crate::pattern::solid_pattern;
 use crate::ray::{ray, Ray};
 use crate::shapes::Sphere;
 use crate::transformations::scale;
-use crate::tuple::{point, Tuple};
+use crate::tuple::{point, vector, Tuple};
+use uuid::Uuid;
 pub struct World {
     pub objects: Vec<Box<dyn Object>>,
     pub light_sources: Vec<PointLight>,
@@ -40,6 +41,11 @@ impl World {
     }
 
     pub fn color_at(&self, r: Ray, remaining: usize) -> Color {
+        self.trace(r, remaining).0
+    }
+
+    // the color seen along the ray and the distance to the surface it came from
+    fn trace(&self, r: Ray, remaining: usize) -> (Color, f64) {
         let intersections = self.intersect(r);
         let mut xs: Vec<&Intersection> = intersections.iter().map(|i| i).collect();
         let hit_option = xs.hit();
@@ -47,9 +53,9 @@ impl World {
         match hit_option {
             Some(hit) => {
                 let comps = hit.prepare(r, &intersections);
-                self.shade_hit(comps, remaining)
+                (self.shade_hit(comps, remaining), hit.t)
             }
-            None => color(0, 0, 0),
+            None => (color(0, 0, 0), f64::INFINITY),
         }
     }
 
@@ -58,43 +64,92 @@ impl World {
         for obj in self.objects.iter() {
             xs.append(&mut obj.intersect(r).unwrap());
         }
+        xs.retain(|i| {
+            let point = i.object.rest_pose_at(r.time) * r.position(i.t);
+            !i.object.material().is_cut_out(i.object, point)
+        });
 
         xs.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
         xs
     }
 
     fn shade_hit(&self, comps: ComputedIntersection, remaining: usize) -> Color {
-        self.light_sources
+        let shading = self.shade(&comps, remaining);
+        shading.direct + shading.reflection + shading.refraction
+    }
+
+    // reflection and refraction don't depend on the lights, so they're
+    // traced once however many lights there are
+    fn shade(&self, comps: &ComputedIntersection, remaining: usize) -> Shading {
+        let albedo = comps
+            .object
+            .material()
+            .albedo_at(comps.object, comps.rest_point);
+        let direct = self
+            .light_sources
             .iter()
             .fold(color(0, 0, 0), |color, light_source| {
-                let surface = color
-                    + comps.object.material().lighting(
-                        comps.object,
+                color
+                    + comps.object.material().lighting_with_albedo(
+                        albedo,
                         light_source,
                         comps.over_point,
                         comps.eye_v,
                         comps.normal_v,
-                        self.is_shadowed(comps.over_point, light_source),
-                    );
-                let reflected = self.reflected_color(&comps, remaining);
-                let refracted = self.refracted_color(&comps, remaining);
+                        self.is_shadowed(comps.over_point, light_source, comps.time),
+                    )
+            });
+        let mut reflection = self.reflected_color(comps, remaining);
+        let mut refraction = self.refracted_color(comps, remaining);
+
+        let material = comps.object.material();
+
+        // fresnel effect
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            reflection = reflection * reflectance;
+            refraction = refraction * (1.0 - reflectance);
+        }
 
-                let material = comps.object.material();
+        Shading {
+            albedo,
+            direct,
+            reflection,
+            refraction,
+        }
+    }
+
+    // like `color_at`, but also reports what the ray hit and how its colour
+    // breaks down, for render passes
+    pub fn sample_at(&self, r: Ray, remaining: usize) -> Sample {
+        let intersections = self.intersect(r);
+        let mut xs: Vec<&Intersection> = intersections.iter().collect();
 
-                // fresnel effect
-                if material.reflective > 0.0 && material.transparency > 0.0 {
-                    let reflectance = comps.schlick();
-                    return surface + reflected * reflectance + refracted * (1.0 - reflectance);
+        match xs.hit() {
+            Some(hit) => {
+                let comps = hit.prepare(r, &intersections);
+                let shading = self.shade(&comps, remaining);
+                Sample {
+                    color: shading.direct + shading.reflection + shading.refraction,
+                    depth: hit.t,
+                    normal: comps.normal_v,
+                    albedo: shading.albedo,
+                    object_id: Some(comps.object.id()),
+                    direct: shading.direct,
+                    reflection: shading.reflection,
+                    refraction: shading.refraction,
                 }
-                surface + reflected + refracted
-            })
+            }
+            None => Sample::default(),
+        }
     }
 
-    fn is_shadowed(&self, point: Tuple, light_source: &PointLight) -> bool {
+    // shadows from moving objects depend on when during the shutter we look
+    fn is_shadowed(&self, point: Tuple, light_source: &PointLight, time: f64) -> bool {
         let v = light_source.position - point;
         let distance = v.magnitude();
         let direction = v.normalize();
-        let r = Ray::new(point, direction);
+        let r = Ray::new(point, direction).with_time(time);
 
         let intersections = self.intersect(r);
         let mut xs: Vec<&Intersection> = intersections.iter().map(|i| i).collect();
@@ -110,8 +165,15 @@ impl World {
         if comps.object.material().reflective == 0.0 || remaining <= 0 {
             color(0, 0, 0)
         } else {
-            let reflect_ray = ray(comps.over_point, comps.reflect_v);
-            self.color_at(reflect_ray, remaining - 1) * comps.object.material().reflective
+            let reflect_ray = ray(comps.over_point, comps.reflect_v)
+                .with_wavelength(comps.wavelength)
+                .with_time(comps.time);
+            let (reflected, distance) = self.trace(reflect_ray, remaining - 1);
+            let attenuation = match comps.reflected_medium {
+                Some(medium) => medium.material().transmittance(distance),
+                None => WHITE,
+            };
+            reflected * attenuation * comps.object.material().reflective
         }
     }
 
@@ -128,13 +190,63 @@ impl World {
             } else {
                 let cos_t = (1.0 - sin2_t).sqrt();
                 let direction = comps.normal_v * (n_ratio * cos_i - cos_t) - comps.eye_v * n_ratio;
-                let refract_ray = ray(comps.under_point, direction);
-                self.color_at(refract_ray, remaining - 1) * comps.object.material().transparency
+                let refract_ray = ray(comps.under_point, direction)
+                    .with_wavelength(comps.wavelength)
+                    .with_time(comps.time);
+                let (refracted, distance) = self.trace(refract_ray, remaining - 1);
+                let attenuation = match comps.medium {
+                    Some(medium) => medium.material().transmittance(distance),
+                    None => WHITE,
+                };
+                refracted * attenuation * comps.object.material().transparency
             }
         }
     }
 }
 
+struct Shading {
+    albedo: Color,
+    direct: Color,
+    reflection: Color,
+    refraction: Color,
+}
+
+// a ray's colour along with the surface information render passes need.
+// misses are black, with zero depth, a zero normal and no object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub color: Color,
+    pub depth: f64,
+    pub normal: Tuple,
+    pub albedo: Color,
+    pub object_id: Option<Uuid>,
+    pub direct: Color,
+    pub reflection: Color,
+    pub refraction: Color,
+}
+
+impl Sample {
+    // light that arrived by bouncing off or passing through other surfaces
+    pub fn indirect(&self) -> Color {
+        self.reflection + self.refraction
+    }
+}
+
+impl Default for Sample {
+    fn default() -> Sample {
+        Sample {
+            color: BLACK,
+            depth: 0.0,
+            normal: vector(0, 0, 0),
+            albedo: BLACK,
+            object_id: None,
+            direct: BLACK,
+            reflection: BLACK,
+            refraction: BLACK,
+        }
+    }
+}
+
 pub fn world() -> World {
     World {
         objects: vec![],
@@ -145,13 +257,12 @@ pub fn world() -> World {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::color::BLACK;
     use crate::intersection::intersection;
-    use crate::pattern::test_pattern;
+    use crate::motion::Motion;
+    use crate::pattern::{stripe_pattern, test_pattern};
     use crate::ray::ray;
     use crate::shapes::Plane;
-    use crate::transformations::translate;
-    use crate::tuple::vector;
+    use crate::transformations::{identity, rotate_x, scale, translate};
     use std::f64::consts::PI;
 
     #[test]
@@ -262,7 +373,7 @@ mod tests {
         let w = World::default();
         let p = point(0, 10, 0);
 
-        assert!(w.is_shadowed(p, &w.light_sources[0]) == false);
+        assert!(w.is_shadowed(p, &w.light_sources[0], 0.0) == false);
     }
 
     #[test]
@@ -270,7 +381,7 @@ mod tests {
         let w = World::default();
         let p = point(10, -10, 10);
 
-        assert!(w.is_shadowed(p, &w.light_sources[0]) == true);
+        assert!(w.is_shadowed(p, &w.light_sources[0], 0.0) == true);
     }
 
     #[test]
@@ -278,7 +389,7 @@ mod tests {
         let w = World::default();
         let p = point(-20, 20, -20);
 
-        assert!(w.is_shadowed(p, &w.light_sources[0]) == false);
+        assert!(w.is_shadowed(p, &w.light_sources[0], 0.0) == false);
     }
 
     #[test]
@@ -286,7 +397,18 @@ mod tests {
         let w = World::default();
         let p = point(-2, 2, -2);
 
-        assert!(w.is_shadowed(p, &w.light_sources[0]) == false);
+        assert!(w.is_shadowed(p, &w.light_sources[0], 0.0) == false);
+    }
+
+    #[test]
+    fn shadows_of_moving_objects_depend_on_time() {
+        let mut w = World::default();
+        w.objects[0].set_motion(Motion::new(identity(), translate(0, 5, 0)).unwrap());
+        w.objects[1].set_motion(Motion::new(scale(0.5, 0.5, 0.5), translate(0, 5, 0)).unwrap());
+        let p = point(10, -10, 10);
+
+        assert!(w.is_shadowed(p, &w.light_sources[0], 0.0));
+        assert!(!w.is_shadowed(p, &w.light_sources[0], 1.0));
     }
 
     #[test]
@@ -356,6 +478,26 @@ mod tests {
         assert!(c == color(0.87677, 0.92436, 0.82918));
     }
 
+    #[test]
+    fn reflections_are_not_repeated_for_each_light() {
+        let mut w = World::default();
+        let mut s = Plane::default();
+        s.material_mut().reflective = 0.5;
+        s.transform(translate(0, -1, 0));
+        w.objects.append(&mut vec![Box::new(s)]);
+        let half = PointLight::new(point(-10, 10, -10), color(0.5, 0.5, 0.5));
+        w.light_sources = vec![half, half];
+
+        let root_2 = f64::sqrt(2.0);
+        let r = ray(point(0, 0, -3), vector(0, -root_2 / 2.0, root_2 / 2.0));
+        let i = intersection(root_2, w.objects[2].as_ref());
+
+        let i2 = i.clone();
+        let comps = i.prepare(r, &[i2]);
+        let c = w.shade_hit(comps, 4);
+        assert!(c == color(0.87677, 0.92436, 0.82918));
+    }
+
     #[test]
     fn reflected_color_at_max_recursion() {
         let mut w = World::default();
@@ -544,4 +686,134 @@ mod tests {
         let c = w.shade_hit(comps, 5);
         assert!(c == color(0.93391, 0.69643, 0.69243));
     }
+
+    #[test]
+    fn refracted_color_is_attenuated_by_distance_through_medium() {
+        let mut glass = Sphere::default();
+        glass.material.ambient = 0.0;
+        glass.material.diffuse = 0.0;
+        glass.material.specular = 0.0;
+        glass.material.transparency = 1.0;
+        glass.material.refractive_index = 1.0;
+        glass.material.absorption = color(0.5, 0.5, 0.5);
+        glass.material.density = 1.0;
+
+        let mut backdrop = Plane::default();
+        backdrop.transform(rotate_x(PI / 2.0).translate(0.0, 0.0, 3.0));
+        backdrop.material.ambient = 1.0;
+        backdrop.material.diffuse = 0.0;
+        backdrop.material.specular = 0.0;
+
+        let w = World::new(
+            vec![Box::new(glass), Box::new(backdrop)],
+            vec![PointLight::new(point(-10, 10, -10), color(1, 1, 1))],
+        );
+
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let c = w.color_at(r, 5);
+        assert!(c == color(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn reflections_inside_a_medium_are_attenuated_too() {
+        let mut glass = Sphere::default();
+        glass.transform(scale(3, 3, 3));
+        glass.material.ambient = 1.0;
+        glass.material.diffuse = 0.0;
+        glass.material.specular = 0.0;
+        glass.material.transparency = 1.0;
+        glass.material.refractive_index = 1.0;
+        glass.material.absorption = color(0.5, 0.5, 0.5);
+        glass.material.density = 1.0;
+
+        let mut mirror = Sphere::default();
+        mirror.material.reflective = 1.0;
+
+        let w = World::new(
+            vec![Box::new(glass), Box::new(mirror)],
+            vec![PointLight::new(point(-10, 10, -10), color(1, 1, 1))],
+        );
+
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+        let xs = vec![
+            intersection(2, w.objects[0].as_ref()),
+            intersection(4, w.objects[1].as_ref()),
+            intersection(6, w.objects[1].as_ref()),
+            intersection(8, w.objects[0].as_ref()),
+        ];
+        let comps = xs[1].prepare(r, &xs);
+
+        // the reflection travels 2 units back through the glass
+        assert!(w.reflected_color(&comps, 5) == color(0.25, 0.25, 0.25));
+    }
+
+    fn masked_card() -> Plane {
+        let mut card = Plane::default();
+        card.material.mask = Some(stripe_pattern(WHITE, BLACK, None));
+        card
+    }
+
+    #[test]
+    fn rays_pass_through_cut_out_parts_of_a_surface() {
+        let w = World::new(vec![Box::new(masked_card())], vec![]);
+
+        let xs = w.intersect(ray(point(0.5, 1, 0), vector(0, -1, 0)));
+        assert!(xs.len() == 1);
+
+        let xs = w.intersect(ray(point(1.5, 1, 0), vector(0, -1, 0)));
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn cut_out_parts_of_a_surface_cast_no_shadow() {
+        let mut card = masked_card();
+        card.transform(translate(0, 5, 0));
+        let w = World::new(
+            vec![Box::new(card)],
+            vec![PointLight::new(point(0, 10, 0), color(1, 1, 1))],
+        );
+
+        assert!(w.is_shadowed(point(0.5, 0, 0), &w.light_sources[0], 0.0));
+        assert!(!w.is_shadowed(point(3, 0, 0), &w.light_sources[0], 0.0));
+    }
+
+    #[test]
+    fn sampling_a_hit_describes_the_surface() {
+        let w = World::default();
+        let r = ray(point(0, 0, -5), vector(0, 0, 1));
+
+        let sample = w.sample_at(r, 5);
+
+        assert!(sample.color == w.color_at(r, 5));
+        assert!(sample.depth == 4.0);
+        assert!(sample.normal == vector(0, 0, -1));
+        assert!(sample.albedo == color(0.8, 1.0, 0.6));
+        assert!(sample.object_id == Some(w.objects[0].id()));
+        assert!(sample.direct == sample.color);
+        assert!(sample.indirect() == BLACK);
+    }
+
+    #[test]
+    fn sampling_a_reflective_surface_separates_the_reflection() {
+        let mut w = World::default();
+        let mut s = Plane::default();
+        s.material_mut().reflective = 0.5;
+        s.transform(translate(0, -1, 0));
+        w.objects.append(&mut vec![Box::new(s)]);
+
+        let root_2 = f64::sqrt(2.0);
+        let r = ray(point(0, 0, -3), vector(0, -root_2 / 2.0, root_2 / 2.0));
+        let sample = w.sample_at(r, 4);
+
+        assert!(sample.reflection == color(0.19033, 0.23791, 0.14274));
+        assert!(sample.direct + sample.indirect() == sample.color);
+    }
+
+    #[test]
+    fn sampling_a_miss_is_empty() {
+        let w = World::default();
+        let r = ray(point(0, 0, -5), vector(0, 1, 0));
+
+        assert!(w.sample_at(r, 5) == Sample::default());
+    }
 }